@@ -1,4 +1,4 @@
-//! Auto derive FromConfig.
+//! Auto derive FromConfig and ToConfig.
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![warn(
     anonymous_parameters,
@@ -36,16 +36,66 @@ pub fn derive_config(input: proc_macro::TokenStream) -> proc_macro::TokenStream
     proc_macro::TokenStream::from(body)
 }
 
+#[allow(missing_docs)]
+#[proc_macro_derive(ToConfig, attributes(config, validate))]
+pub fn derive_to_config(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input: DeriveInput = parse_macro_input!(input as DeriveInput);
+    let name = input.ident.clone();
+    let body = match input.data {
+        Data::Struct(data) => derive_to_config_struct(&name, input.attrs, data),
+        _ => Err(Error::new_spanned(name, "Only support struct")),
+    };
+    let body = match body {
+        Ok(tokens) => tokens,
+        Err(err) => err.to_compile_error(),
+    };
+    proc_macro::TokenStream::from(body)
+}
+
+fn derive_to_config_struct(
+    name: &Ident,
+    attrs: Vec<Attribute>,
+    data: DataStruct,
+) -> Result<TokenStream> {
+    let StructInfo {
+        crate_path: cfg_crate_path,
+        rename_all,
+        ..
+    } = derive_config_attrs(attrs)?;
+    let fields = derive_config_fields(data, rename_all.as_deref())?;
+    let set_fields = fields.iter().filter(|f| !f.skip).map(|f| {
+        let name = &f.name;
+        let key = f.ren.as_str();
+        quote! {
+            builder.set_config(#key, &self.#name)?;
+        }
+    });
+    Ok(quote! {
+        #[automatically_derived]
+        impl #cfg_crate_path::ToConfig for #name {
+            fn to_config(
+                &self,
+                builder: &mut #cfg_crate_path::source::ConfigSourceBuilder<'_>,
+            ) -> ::core::result::Result<(), #cfg_crate_path::ConfigError> {
+                #(#set_fields)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
+
 fn derive_config_struct(
     name: &Ident,
     attrs: Vec<Attribute>,
     data: DataStruct,
 ) -> Result<TokenStream> {
-    // Resolve cfg-rs crate path without relying on proc_macro_crate.
-    // Default to ::cfg_rs, allow override via #[config(crate = "your_crate_name")]
-    let mut cfg_crate_path = quote!(::cfg_rs);
+    let StructInfo {
+        prefix,
+        crate_path: cfg_crate_path,
+        rename_all,
+    } = derive_config_attrs(attrs)?;
 
-    let prefix = match derive_config_prefix(attrs, &mut cfg_crate_path) {
+    let prefix = match prefix {
         Some(p) => quote! {
             #[automatically_derived]
             impl #cfg_crate_path::FromConfigWithPrefix for #name {
@@ -57,7 +107,7 @@ fn derive_config_struct(
         _ => quote! {},
     };
 
-    let fields = derive_config_fields(data)?;
+    let fields = derive_config_fields(data, rename_all.as_deref())?;
     let fs: Vec<Ident> = fields.iter().map(|f| f.name.clone()).collect();
     #[cfg(feature = "regex")]
     let regex_map = {
@@ -118,33 +168,93 @@ fn derive_config_struct(
     })
 }
 
-fn derive_config_prefix(attrs: Vec<Attribute>, crate_path: &mut TokenStream) -> Option<String> {
-    let mut prefix = None;
+struct StructInfo {
+    prefix: Option<String>,
+    crate_path: TokenStream,
+    rename_all: Option<String>,
+}
+
+fn derive_config_attrs(attrs: Vec<Attribute>) -> Result<StructInfo> {
+    // Resolve cfg-rs crate path without relying on proc_macro_crate.
+    // Default to ::cfg_rs, allow override via #[config(crate = "your_crate_name")]
+    let mut info = StructInfo {
+        prefix: None,
+        crate_path: quote!(::cfg_rs),
+        rename_all: None,
+    };
     for attr in attrs {
         if attr.path().is_ident("config") {
             attr.parse_nested_meta(|meta| {
                 if meta.path.is_ident("prefix") {
                     let value = meta.value()?;
                     let s: LitStr = value.parse()?;
-                    prefix = Some(s.value());
+                    info.prefix = Some(s.value());
                     Ok(())
                 } else if meta.path.is_ident("crate") {
                     let value = meta.value()?;
                     let s: LitStr = value.parse()?;
                     let ident = Ident::new(&s.value(), s.span());
-                    *crate_path = quote!(#ident);
+                    info.crate_path = quote!(#ident);
+                    Ok(())
+                } else if meta.path.is_ident("rename_all") {
+                    let value = meta.value()?;
+                    let s: LitStr = value.parse()?;
+                    if !RENAME_RULES.contains(&s.value().as_str()) {
+                        return Err(Error::new(
+                            s.span(),
+                            format!("Only support rename_all: {}", RENAME_RULES.join("/")),
+                        ));
+                    }
+                    info.rename_all = Some(s.value());
                     Ok(())
                 } else {
-                    Err(meta.error("Only support prefix"))
+                    Err(meta.error("Only support prefix/crate/rename_all"))
                 }
-            })
-            .unwrap();
+            })?;
         }
-        if prefix.is_some() {
-            break;
+    }
+    Ok(info)
+}
+
+const RENAME_RULES: &[&str] = &[
+    "lowercase",
+    "UPPERCASE",
+    "PascalCase",
+    "camelCase",
+    "snake_case",
+    "SCREAMING_SNAKE_CASE",
+    "kebab-case",
+    "SCREAMING-KEBAB-CASE",
+];
+
+fn rename_field(rule: &str, name: &str) -> String {
+    let pascal = || {
+        name.split('_')
+            .map(|w| {
+                let mut c = w.chars();
+                match c.next() {
+                    Some(f) => f.to_uppercase().chain(c).collect(),
+                    None => String::new(),
+                }
+            })
+            .collect::<String>()
+    };
+    match rule {
+        "lowercase" => name.to_lowercase(),
+        "UPPERCASE" | "SCREAMING_SNAKE_CASE" => name.to_uppercase(),
+        "PascalCase" => pascal(),
+        "camelCase" => {
+            let v = pascal();
+            let mut c = v.chars();
+            match c.next() {
+                Some(f) => f.to_lowercase().chain(c).collect(),
+                None => v,
+            }
         }
+        "kebab-case" => name.replace('_', "-"),
+        "SCREAMING-KEBAB-CASE" => name.to_uppercase().replace('_', "-"),
+        _ => name.to_string(),
     }
-    prefix
 }
 
 struct FieldInfo {
@@ -153,28 +263,35 @@ struct FieldInfo {
     ren: String,
     desc: Option<String>,
     ty: Type,
+    skip: bool,
+    secret: bool,
     validates: Vec<ValidateRule>,
 }
 
-fn derive_config_fields(data: DataStruct) -> Result<Vec<FieldInfo>> {
+fn derive_config_fields(data: DataStruct, rename_all: Option<&str>) -> Result<Vec<FieldInfo>> {
     if let Fields::Named(fields) = data.fields {
         let mut fs = vec![];
         for field in fields.named {
-            fs.push(derive_config_field(field)?);
+            fs.push(derive_config_field(field, rename_all)?);
         }
         return Ok(fs);
     }
     Err(Error::new_spanned(data.fields, "Only support named body"))
 }
 
-fn derive_config_field(field: Field) -> Result<FieldInfo> {
+fn derive_config_field(field: Field, rename_all: Option<&str>) -> Result<FieldInfo> {
     let name = field.ident.expect("Not possible");
     let mut f = FieldInfo {
-        ren: name.to_string(),
+        ren: match rename_all {
+            Some(rule) => rename_field(rule, &name.to_string()),
+            None => name.to_string(),
+        },
         name,
         def: None,
         desc: None,
+        secret: is_secret(option_inner(&field.ty).unwrap_or(&field.ty)),
         ty: field.ty.clone(),
+        skip: false,
         validates: vec![],
     };
     derive_config_field_attr(&mut f, field.attrs)?;
@@ -191,8 +308,12 @@ fn derive_config_field_attr(f: &mut FieldInfo, attrs: Vec<Attribute>) -> Result<
                     f.ren = parse_lit(meta.value()?.parse::<Lit>()?);
                 } else if meta.path.is_ident("desc") {
                     f.desc = Some(parse_lit(meta.value()?.parse::<Lit>()?));
+                } else if meta.path.is_ident("skip") {
+                    f.skip = true;
+                } else if meta.path.is_ident("secret") {
+                    f.secret = true;
                 } else {
-                    return Err(meta.error("Only support default/name/desc"));
+                    return Err(meta.error("Only support default/name/desc/skip/secret"));
                 }
                 Ok(())
             })?;
@@ -379,19 +500,29 @@ fn build_parse_and_validate(
 ) -> TokenStream {
     let name = &field.name;
     let ty = &field.ty;
+    if field.skip {
+        return quote! {
+            let #name: #ty = ::core::default::Default::default();
+        };
+    }
     let key = field.ren.as_str();
     let def = match &field.def {
         Some(d) => quote! {,Some(#d.into())},
         None => quote! {,None},
     };
     let validate = build_validate_block(field, crate_path, regex_map);
+    let parse = if field.secret {
+        quote! { parse_secret_config }
+    } else {
+        quote! { parse_config }
+    };
     if field.validates.is_empty() {
         quote! {
-            let #name: #ty = context.parse_config(#key #def)?;
+            let #name: #ty = context.#parse(#key #def)?;
         }
     } else {
         quote! {
-            let #name: #ty = context.parse_config(#key #def)?;
+            let #name: #ty = context.#parse(#key #def)?;
             #validate
         }
     }
@@ -575,6 +706,17 @@ fn wrap_validate_call(
     }
 }
 
+fn is_secret(ty: &Type) -> bool {
+    let Type::Path(type_path) = ty else {
+        return false;
+    };
+    type_path
+        .path
+        .segments
+        .last()
+        .is_some_and(|s| s.ident == "Secret")
+}
+
 fn option_inner(ty: &Type) -> Option<&Type> {
     let Type::Path(type_path) = ty else {
         return None;
@@ -34,6 +34,7 @@ pub struct PostgresConfig {
     port: Option<u16>,
     #[config(default = "postgres")]
     user: String,
+    #[config(secret)]
     password: Option<String>,
     dbname: Option<String>,
     options: Option<String>,
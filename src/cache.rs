@@ -58,6 +58,14 @@ impl<L: ConfigSource> CacheConfigSource<L> {
             origin,
         }
     }
+
+    /// Create source with values loaded from `origin`.
+    pub(crate) fn with_values(origin: L, values: HashSource) -> Self {
+        Self {
+            cache: Mutex::new((Some(values), false)),
+            origin,
+        }
+    }
 }
 
 impl<L: ConfigSource> ConfigSource for CacheConfigSource<L> {
@@ -3,22 +3,34 @@ use std::{
     borrow::Borrow,
     cell::RefCell,
     collections::HashSet,
-    env::var,
-    path::PathBuf,
+    env::{var, vars_os},
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
 use crate::{
-    FromConfig, FromConfigWithPrefix, PartialKeyCollector,
+    FromConfig, FromConfigWithPrefix, PartialKeyCollector, ToConfig,
     cache::CacheConfigSource,
-    err::ConfigError,
+    err::{ConfigError, similar},
+    export::{Format, export, glob_match, redact},
+    expr::{Value, eval, is_expression},
     impl_cache,
     key::{CacheString, ConfigKey, PartialKeyIter},
     macros::{cfg_log, impl_default},
+    provenance::{Provenance, trace_expansion, with_trace},
+    render::{parse as parse_template, render as render_template},
+    resolver::{PlaceholderResolver, resolve_base64, resolve_env, resolve_file},
     source::{
-        ConfigSource, SourceOption, cargo::Cargo, environment::PrefixEnvironment,
-        memory::HashSource, register_by_ext, register_files,
+        ConfigSource, Imports, SourceOption,
+        cargo::Cargo,
+        environment::PrefixEnvironment,
+        file::{DirLoader, Section},
+        loader_by_ext,
+        memory::HashSource,
+        register_by_ext, register_files, split_profile_names,
     },
-    value::ConfigValue,
+    tree::ConfigTree,
+    value::{CoercionPolicy, ConfigValue},
     value_ref::Refresher,
 };
 
@@ -30,7 +42,11 @@ use crate::{
 pub struct ConfigContext<'a> {
     key: ConfigKey<'a>,
     source: &'a HashSource,
+    base_policy: CoercionPolicy,
+    policy: CoercionPolicy,
     pub(crate) ref_value_flag: bool,
+    /// Whether looked up keys are marked as used, see [`Configuration::unused_keys`].
+    track: bool,
 }
 
 struct CacheValue {
@@ -59,7 +75,10 @@ impl HashSource {
         ConfigContext {
             key: cache.new_key(),
             source: self,
+            base_policy: self.policy,
+            policy: self.policy,
             ref_value_flag: false,
+            track: true,
         }
     }
 }
@@ -69,11 +88,30 @@ impl<'a> ConfigContext<'a> {
         &self.source.refs
     }
 
+    /// Read value of `key` referenced by placeholder as text.
+    fn read_placeholder_key(
+        source: &HashSource,
+        key: &str,
+        history: &mut HashSet<String>,
+        track: bool,
+    ) -> Result<String, ConfigError> {
+        CacheString::with_key_place(|cache| {
+            let mut context = source.new_context(cache);
+            context.track = track;
+            // Placeholders render values as text, whatever their types are.
+            if context.base_policy == CoercionPolicy::Strict {
+                context.base_policy = CoercionPolicy::Default;
+            }
+            context.do_parse_config::<String, &str>(key, None, history)
+        })
+    }
+
     fn parse_placeholder(
         source: &'a HashSource,
         current_key: &ConfigKey<'_>,
         val: &str,
         history: &mut HashSet<String>,
+        track: bool,
     ) -> Result<(bool, Option<ConfigValue<'a>>), ConfigError> {
         let pat: &[_] = &['$', '\\', '}'];
         CacheValue::with_key(move |cv| {
@@ -114,25 +152,69 @@ impl<'a> ConfigContext<'a> {
 
                         cv.buf.push_str(&value[..pos]);
                         let v = &(cv.buf.as_str())[last..];
+                        if source.expressions && is_expression(v) {
+                            let ret = eval(&current_key.to_string(), v, &mut |key| {
+                                if !history.insert(key.to_string()) {
+                                    return Err(ConfigError::ConfigRecursiveError(
+                                        current_key.to_string(),
+                                    ));
+                                }
+                                let ret =
+                                    match Self::read_placeholder_key(source, key, history, track) {
+                                        Err(e) => Err(e.map_kind(|e| match e {
+                                            ConfigError::ConfigNotFound(v) => {
+                                                ConfigError::ConfigRecursiveNotFound(v)
+                                            }
+                                            e => e,
+                                        })),
+                                        ret => ret,
+                                    };
+                                history.remove(key);
+                                if let Ok(v) = &ret {
+                                    trace_expansion(key, v);
+                                }
+                                ret.map(Value::infer)
+                            })?
+                            .to_string();
+                            trace_expansion(v, &ret);
+                            cv.buf.truncate(last);
+                            cv.buf.push_str(&ret);
+                            value = &value[pos + 1..];
+                            continue;
+                        }
+                        // Registered schemes take precedence over `key:default`.
+                        let resolver = v
+                            .split_once(':')
+                            .and_then(|(scheme, v)| source.resolver(scheme).map(|r| (r, v)));
                         let (key, def) = match v.find(':') {
+                            _ if resolver.is_some() => (v, None),
                             Some(pos) => (&v[..pos], Some(&v[pos + 1..])),
                             _ => (v, None),
                         };
                         if !history.insert(key.to_string()) {
                             return Err(ConfigError::ConfigRecursiveError(current_key.to_string()));
                         }
-                        let v = match CacheString::with_key_place(|cache| {
-                            source
-                                .new_context(cache)
-                                .do_parse_config::<String, &str>(key, None, history)
-                        }) {
-                            Err(ConfigError::ConfigNotFound(v)) => match def {
-                                Some(v) => v.to_owned(),
-                                _ => return Err(ConfigError::ConfigRecursiveNotFound(v)),
+                        let v = match resolver {
+                            Some((r, v)) => r.resolve(v)?,
+                            None => match Self::read_placeholder_key(source, key, history, track) {
+                                Err(e) if matches!(e.kind(), ConfigError::ConfigNotFound(_)) => {
+                                    match def {
+                                        Some(v) => v.to_owned(),
+                                        _ => {
+                                            return Err(e.map_kind(|e| match e {
+                                                ConfigError::ConfigNotFound(v) => {
+                                                    ConfigError::ConfigRecursiveNotFound(v)
+                                                }
+                                                e => e,
+                                            }));
+                                        }
+                                    }
+                                }
+                                ret => ret?,
                             },
-                            ret => ret?,
                         };
                         history.remove(key);
+                        trace_expansion(key, &v);
                         cv.buf.truncate(last);
                         cv.buf.push_str(&v);
                         value = &value[pos + 1..];
@@ -163,30 +245,99 @@ impl<'a> ConfigContext<'a> {
         default_value: Option<ConfigValue<'_>>,
         history: &mut HashSet<String>,
     ) -> Result<T, ConfigError> {
+        self.do_parse_with(partial_key, default_value, history, T::from_config)
+    }
+
+    /// Push partial key, resolve its value, then build result by `f`.
+    #[inline]
+    pub(crate) fn do_parse_with<T, K, F>(
+        &mut self,
+        partial_key: K,
+        default_value: Option<ConfigValue<'_>>,
+        history: &mut HashSet<String>,
+        f: F,
+    ) -> Result<T, ConfigError>
+    where
+        K: Into<PartialKeyIter<'a>>,
+        F: FnOnce(&mut Self, Option<ConfigValue<'_>>) -> Result<T, ConfigError>,
+    {
         let mark = self.key.push(partial_key);
-        let value = match self.source.get_value(&self.key).or(default_value) {
-            Some(ConfigValue::StrRef(s)) => {
-                match Self::parse_placeholder(self.source, &self.key, s, history)? {
-                    (true, _) => Some(ConfigValue::StrRef(s)),
-                    (false, v) => v,
-                }
+        if self.track {
+            self.source.mark_used(self.key.as_str());
+        }
+        // Default values and resolved placeholders are always text, so they are not rejected by strict policy.
+        let mut generated = false;
+        let value = match self.source.lookup(&self.key, self.track) {
+            Ok(Some(v)) => Ok(Some(v)),
+            Ok(None) => {
+                generated = default_value.is_some();
+                Ok(default_value)
             }
-            Some(ConfigValue::Str(s)) => {
-                match Self::parse_placeholder(self.source, &self.key, &s, history)? {
-                    (true, _) => Some(ConfigValue::Str(s)),
-                    (_, v) => v,
+            Err(e) => Err(e),
+        };
+        let value = match value {
+            Err(e) => Err(e),
+            Ok(Some(ConfigValue::StrRef(s))) => {
+                Self::parse_placeholder(self.source, &self.key, s, history, self.track).map(|v| {
+                    match v {
+                        (true, _) => Some(ConfigValue::StrRef(s)),
+                        (false, v) => {
+                            generated = true;
+                            v
+                        }
+                    }
+                })
+            }
+            Ok(Some(ConfigValue::Str(s))) => {
+                match Self::parse_placeholder(self.source, &self.key, &s, history, self.track) {
+                    Ok((true, _)) => Ok(Some(ConfigValue::Str(s))),
+                    Ok((_, v)) => {
+                        generated = true;
+                        Ok(v)
+                    }
+                    Err(e) => Err(e),
                 }
             }
             #[cfg(feature = "rand")]
-            Some(ConfigValue::Rand(s)) => Some(s.normalize()),
+            Ok(Some(ConfigValue::Rand(s))) => Ok(Some(s.normalize())),
             v => v,
         };
 
-        let v = T::from_config(self, value);
+        let v = value
+            .and_then(|value| {
+                let policy = self.policy;
+                self.policy = match self.base_policy {
+                    CoercionPolicy::Strict if generated => CoercionPolicy::Default,
+                    p => p,
+                };
+                let v = (f)(self, value);
+                self.policy = policy;
+                v
+            })
+            .map_err(|e| self.add_context(e, type_name::<T>()));
         self.key.pop(mark);
         v
     }
 
+    /// Add current key, type and origin of value to error.
+    fn add_context(&self, e: ConfigError, tp: &'static str) -> ConfigError {
+        let key = self.current_key();
+        e.with_context(|c| {
+            if c.key.is_none() {
+                if let Some((source, location)) = self.source.origin_of(self.key.as_str()) {
+                    c.source = Some(source.to_string());
+                    if let Some(l) = location {
+                        c.path = Some(l.path.clone());
+                        c.line = l.line;
+                        c.column = l.column;
+                    }
+                }
+                c.key = Some(key.clone());
+            }
+            c.frames.insert(0, (key, tp));
+        })
+    }
+
     /// Parse partial config by partial key and default value.
     #[inline]
     pub fn parse_config<T: FromConfig>(
@@ -203,11 +354,48 @@ impl<'a> ConfigContext<'a> {
         self.key.to_string()
     }
 
-    #[allow(dead_code)]
+    #[cfg(feature = "log")]
     pub(crate) fn current_key_str(&self) -> &str {
         self.key.as_str()
     }
 
+    /// Parse partial config like [`ConfigContext::parse_config`], but errors never include raw value.
+    ///
+    /// It is used by fields marked with `#[config(secret)]`.
+    #[inline]
+    pub fn parse_secret_config<T: FromConfig>(
+        &mut self,
+        partial_key: &'a str,
+        default_value: Option<ConfigValue<'_>>,
+    ) -> Result<T, ConfigError> {
+        self.do_parse_with(
+            partial_key,
+            default_value,
+            &mut HashSet::new(),
+            |context, value| T::from_config(context, value).map_err(|e| context.redact(e)),
+        )
+        .map_err(|e| self.redact(e))
+    }
+
+    /// Remove raw value from error.
+    pub(crate) fn redact(&self, e: ConfigError) -> ConfigError {
+        e.map_kind(|e| match e {
+            ConfigError::ConfigParseError(key, _) => {
+                ConfigError::ConfigParseError(key, "***".to_owned())
+            }
+            ConfigError::ConfigCause(_) => {
+                ConfigError::ConfigParseError(self.current_key(), "***".to_owned())
+            }
+            e => e,
+        })
+    }
+
+    /// Coercion policy applied to current value, see [`CoercionPolicy`] for details.
+    #[inline]
+    pub fn coercion_policy(&self) -> CoercionPolicy {
+        self.policy
+    }
+
     #[inline]
     pub(crate) fn type_mismatch<T: Any>(&self, value: &ConfigValue<'_>) -> ConfigError {
         let tp = match value {
@@ -215,11 +403,15 @@ impl<'a> ConfigContext<'a> {
             ConfigValue::Str(_) => "String",
             ConfigValue::Int(_) => "Integer",
             ConfigValue::Float(_) => "Float",
+            ConfigValue::BigInt(_) => "BigInt",
+            ConfigValue::Decimal(_) => "Decimal",
             ConfigValue::Bool(_) => "Bool",
+            ConfigValue::Null => "Null",
             #[cfg(feature = "rand")]
             ConfigValue::Rand(_) => "Random",
         };
         ConfigError::ConfigTypeMismatch(self.current_key(), tp, type_name::<T>())
+            .with_context(|c| c.policy = Some(self.policy))
     }
 
     #[inline]
@@ -233,24 +425,150 @@ impl<'a> ConfigContext<'a> {
         ConfigError::ConfigParseError(self.current_key(), value.to_owned())
     }
 
+    /// Check if partial key exists under current key, either as value or as parent of other keys.
+    #[cfg(feature = "serde")]
+    pub(crate) fn contains_key(&mut self, partial_key: &'a str) -> bool {
+        let mark = self.key.push(partial_key);
+        let v = self.is_present();
+        self.key.pop(mark);
+        v
+    }
+
+    /// Check if current key exists, either as value or as parent of other keys.
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_present(&self) -> bool {
+        self.source.contains_key(&self.key)
+    }
+
     pub(crate) fn collect_keys(&self) -> PartialKeyCollector<'a> {
         let mut c = PartialKeyCollector::new();
+        if self.track {
+            self.source.mark_used(self.key.as_str());
+        }
         self.source.collect_keys(&self.key, &mut c);
+        if self.track {
+            for alias in self.source.aliases(self.key.as_str()).unwrap_or_default() {
+                self.source.mark_used(&alias);
+            }
+        }
         c
     }
 }
 
+/// Names of env variables, names which are not unicode are skipped.
+fn var_names() -> impl Iterator<Item = String> {
+    vars_os().filter_map(|(k, _)| k.into_string().ok())
+}
+
+/// Source of file of directory, see [`Configuration::register_dir`].
+fn fragment(file: &Path) -> Result<Box<dyn ConfigSource + Send + 'static>, ConfigError> {
+    Ok(Box::new(CacheConfigSource::new(loader_by_ext(
+        file.to_path_buf(),
+        false,
+    )?)))
+}
+
+/// Directory whose files are changed when refreshing, see [`Configuration::register_dir`].
+struct DirChange {
+    /// Id of directory.
+    group: usize,
+    /// Current files of directory.
+    files: Vec<PathBuf>,
+    /// Sources of files added, built once and kept by [`Configuration::refresh`].
+    added: Vec<(PathBuf, Box<dyn ConfigSource + Send + 'static>)>,
+}
+
+/// Check if `key` is `prefix` or under it.
+pub(crate) fn is_under(key: &str, prefix: &str) -> bool {
+    prefix.is_empty()
+        || key
+            .strip_prefix(prefix)
+            .is_some_and(|v| v.is_empty() || v.starts_with('.') || v.starts_with('['))
+}
+
+/// Named priorities of config sources, see [`Configuration::register_source_with_priority`].
+///
+/// Sources with higher priority override sources with lower priority,
+/// [`Configuration::register_source`] uses priority `0`, which lies between [`Stage::Files`] and [`Stage::Defaults`].
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Default values, lowest priority.
+    Defaults = -1000,
+    /// Config files.
+    Files = 1000,
+    /// Environment variables.
+    Env = 2000,
+    /// Values set by programming or command line arguments, highest priority.
+    Overrides = 3000,
+}
+
+impl From<Stage> for i32 {
+    fn from(stage: Stage) -> Self {
+        stage as i32
+    }
+}
+
+/// How arrays defined by multiple sources are merged, see [`Configuration::set_array_merge`].
+///
+/// Given `hosts = [a, b, c]` in a lower priority source and `hosts = [x]` in a higher priority source:
+///
+/// | Strategy | Result |
+/// |----------|--------|
+/// | [`ArrayMerge::Replace`] | `[x]` |
+/// | [`ArrayMerge::Append`] | `[x, a, b, c]` |
+/// | [`ArrayMerge::MergeByIndex`] | `[x, b, c]` |
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ArrayMerge {
+    /// Array of the highest priority source replaces the whole array.
+    #[default]
+    Replace,
+    /// Arrays of lower priority sources are appended, in priority order.
+    Append,
+    /// Each index is merged independently.
+    MergeByIndex,
+}
+
 /// Configuration Instance, See [Examples](https://github.com/leptonyu/cfg-rs/tree/main/examples),
 /// [How to Initialize Configuration](index.html#how-to-initialize-configuration) for details.
-#[allow(missing_debug_implementations)]
 pub struct Configuration {
     pub(crate) source: HashSource,
     max: usize,
-    loaders: Vec<Box<dyn ConfigSource + Send + 'static>>,
+    /// Sources with priority, and group id of files registered by [`Configuration::register_dir`].
+    loaders: Vec<(i32, Option<usize>, Box<dyn ConfigSource + Send + 'static>)>,
+    /// Directories registered by [`Configuration::register_dir`], indexed by group id.
+    dirs: Vec<DirLoader>,
+    redact: Vec<String>,
+    /// Prefixes of registered env sources, such as `CFG_`.
+    env_prefix: Vec<String>,
+}
+
+impl std::fmt::Debug for Configuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        struct Values<'a>(&'a Configuration);
+        impl std::fmt::Debug for Values<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_map()
+                    .entries(self.0.iter().map(|(k, v)| {
+                        let v: &dyn std::fmt::Debug =
+                            if self.0.is_redacted(k) { &"***" } else { v };
+                        (k, v)
+                    }))
+                    .finish()
+            }
+        }
+        f.debug_struct("Configuration")
+            .field("sources", &self.sources())
+            .field("values", &Values(self))
+            .finish()
+    }
 }
 
 impl_default!(Configuration);
 
+/// Default patterns of keys whose values are redacted.
+pub(crate) const DEFAULT_REDACT_PATTERNS: [&str; 3] = ["*password*", "*secret*", "*token*"];
+
 impl Configuration {
     /// Create an empty [`Configuration`].
     ///
@@ -261,7 +579,130 @@ impl Configuration {
             source: HashSource::new("configuration"),
             max: 64,
             loaders: vec![],
+            dirs: vec![],
+            redact: DEFAULT_REDACT_PATTERNS
+                .iter()
+                .map(|v| v.to_string())
+                .collect(),
+            env_prefix: vec![],
+        }
+    }
+
+    /// Register built-in placeholder resolvers `env`, `file` and `base64`, see [`PlaceholderResolver`].
+    ///
+    /// They are not registered by default, as `${file:...}` reads any file the process can access,
+    /// and registered schemes change the meaning of `${env:default}` style placeholders.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_builtin_resolvers()
+    ///     .register_kv("kv")
+    ///     .set("app.token", "${base64:c2VjcmV0}")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!("secret", config.get::<String>("app.token").unwrap());
+    /// ```
+    pub fn register_builtin_resolvers(self) -> Self {
+        self.register_resolver("env", resolve_env)
+            .register_resolver("file", resolve_file)
+            .register_resolver("base64", resolve_base64)
+    }
+
+    /// Register placeholder resolver of `scheme`, then `${scheme:value}` is resolved by it,
+    /// resolver registered with the same scheme is replaced.
+    ///
+    /// Registered schemes take precedence over `${key:default}`, so `${scheme:value}` no longer reads key `scheme`.
+    /// See [`Configuration::register_builtin_resolvers`] for built-in schemes.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_resolver("upper", |v: &str| Ok(v.to_uppercase()))
+    ///     .register_kv("kv")
+    ///     .set("app.name", "demo")
+    ///     .set("app.title", "${upper:${app.name}}")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!("DEMO", config.get::<String>("app.title").unwrap());
+    /// ```
+    ///
+    /// * `scheme` - Scheme name.
+    /// * `resolver` - Placeholder resolver.
+    pub fn register_resolver<R: PlaceholderResolver + 'static>(
+        mut self,
+        scheme: &str,
+        resolver: R,
+    ) -> Self {
+        let resolver: Arc<dyn PlaceholderResolver> = Arc::new(resolver);
+        match self.source.resolvers.iter_mut().find(|(s, _)| s == scheme) {
+            Some((_, r)) => *r = resolver,
+            None => self.source.resolvers.push((scheme.to_string(), resolver)),
         }
+        self
+    }
+
+    /// Set coercion policy used when converting values, default is [`CoercionPolicy::Default`].
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .set_coercion_policy(CoercionPolicy::Lenient)
+    ///     .register_kv("kv")
+    ///     .set("enabled", "on")
+    ///     .set("port", "8080")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert!(config.get::<bool>("enabled").unwrap());
+    ///
+    /// let config = config.set_coercion_policy(CoercionPolicy::Strict);
+    /// assert!(config.get::<bool>("enabled").is_err());
+    /// assert!(config.get::<u16>("port").is_err());
+    /// ```
+    pub fn set_coercion_policy(mut self, policy: CoercionPolicy) -> Self {
+        self.source.policy = policy;
+        self
+    }
+
+    /// Coercion policy used when converting values, see [`Configuration::set_coercion_policy`].
+    pub fn coercion_policy(&self) -> CoercionPolicy {
+        self.source.policy
+    }
+
+    /// Enable or disable expressions in placeholders, default is disabled.
+    ///
+    /// A placeholder is an expression if its text before the first `:` is not a key, such as `${server.port + 1}`.
+    /// `${key}`, `${key:default}` and `${scheme:value}` are unchanged. Expressions support:
+    ///
+    /// * Integer and float arithmetic: `+`, `-`, `*`, `/`, `%`, `+` also joins strings.
+    /// * Comparisons `==`, `!=`, `<`, `<=`, `>`, `>=`, boolean logic `&&`, `||`, `!` and ternary `c ? a : b`.
+    /// * Literals `1`, `1.5`, `true`, `'text'` or `"text"`, and config keys, values of keys are typed as integers,
+    ///   floats or bools if possible. Numbers of keys keep their text when joined with strings or passed to `str`,
+    ///   so `007` stays `007`.
+    /// * Functions `upper`, `lower`, `trim`, `len`, `concat`, `replace`, `substr`, `contains`, `starts_with`,
+    ///   `ends_with`, `str`, `int`, `float`, `abs`, `min` and `max`.
+    ///
+    /// Expressions have no access to anything but config keys. Use spaces around `-` to subtract,
+    /// as `a-b` is a kebab-case key. Evaluation errors are [`ConfigError::ConfigParseError`]
+    /// with the expression, and `int` fails if the float has a fraction or is out of range.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .set_expressions(true)
+    ///     .register_kv("kv")
+    ///     .set("env", "prod")
+    ///     .set("server.port", 8080)
+    ///     .set("admin.port", "${server.port + 1}")
+    ///     .set("log.level", "${env == 'prod' ? 'warn' : 'debug'}")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(8081, config.get::<u16>("admin.port").unwrap());
+    /// assert_eq!("warn", config.get::<String>("log.level").unwrap());
+    /// ```
+    pub fn set_expressions(mut self, enabled: bool) -> Self {
+        self.source.expressions = enabled;
+        self
     }
 
     /// Register key value manually.
@@ -280,7 +721,27 @@ impl Configuration {
     /// 2. `CFG_APP_0_NAME` => `app[0].name`
     ///
     pub fn register_prefix_env(self, prefix: &str) -> Result<Self, ConfigError> {
-        self.register_source(PrefixEnvironment::new(prefix))
+        self.register_env(PrefixEnvironment::new(prefix), 0)
+    }
+
+    fn register_env(mut self, env: PrefixEnvironment, priority: i32) -> Result<Self, ConfigError> {
+        self.env_prefix.push(env.prefix().to_string());
+        self.register_source_with_priority(env, priority)
+    }
+
+    /// Register all env variables with prefix, and treat variables with value `marker` as explicit null.
+    ///
+    /// * `prefix` - Env variable prefix.
+    /// * `marker` - Null marker, use `""` to treat `CFG_APP_NAME=` as null.
+    ///
+    /// Explicit null removes the key and its sub keys set by sources registered later,
+    /// see [`ConfigValue::Null`].
+    pub fn register_prefix_env_with_null(
+        self,
+        prefix: &str,
+        marker: &str,
+    ) -> Result<Self, ConfigError> {
+        self.register_env(PrefixEnvironment::new(prefix).with_null(marker), 0)
     }
 
     /// Register file source, this method uses file extension[^ext] to choose how to parsing configuration.
@@ -290,6 +751,27 @@ impl Configuration {
     ///
     /// See [Supported File Formats](index.html#supported-file-format) for details.
     ///
+    /// ## Imports
+    ///
+    /// File can import other files by key `app.imports`, paths are relative to the importing file:
+    ///
+    /// ```toml
+    /// [app]
+    /// imports = ["db.toml", "optional:local.toml", "conf.d/*.yaml"]
+    /// ```
+    ///
+    /// * Imports prefixed by `optional:` may not exist, `*` matches any characters of file name, matched files load in lexical order.
+    /// * Imported files load just below the importing file, later imports override earlier ones.
+    ///   Set `app.imports_override = true` to load them just above the importing file.
+    /// * Each imported file is a separate source which refreshes on its own, imports may import other files,
+    ///   cycles return [`ConfigError::ConfigRecursiveError`].
+    /// * Imports are resolved once when registering the file, changes of `app.imports` and files matched by glob
+    ///   later are not picked up by [`Configuration::refresh`].
+    ///
+    /// Files loaded by [`PredefinedConfigurationBuilder::init`] can import files as well.
+    ///
+    /// Profile sections, such as `[_profiles.prod]`, are skipped, see [`PredefinedConfigurationBuilder::init`].
+    ///
     /// [^ext]: `cfg-rs` does not **enable** any file format by default, please enable specific features when use this method.
     pub fn register_file<P: Into<PathBuf>>(
         self,
@@ -299,6 +781,40 @@ impl Configuration {
         register_by_ext(self, path.into(), required)
     }
 
+    /// Register files in directory `path` matching `pattern`, such as drop-in fragments `conf.d/*.toml`.
+    ///
+    /// Each file is registered as a source, files load in lexical order and later files override earlier ones,
+    /// file extension[^ext] is used to choose how to parse each file, files of unsupported formats are skipped.
+    /// `*` matches any characters of file name, missing directory has no files.
+    ///
+    /// [`Configuration::refresh`] detects files added, removed or modified.
+    ///
+    /// ```rust,no_run
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_dir("/etc/myapp/conf.d", "*.toml")
+    ///     .unwrap();
+    /// ```
+    pub fn register_dir<P: Into<PathBuf>>(
+        mut self,
+        path: P,
+        pattern: &str,
+    ) -> Result<Self, ConfigError> {
+        let mut dir = DirLoader::new(path.into(), pattern);
+        let group = Some(self.dirs.len());
+        let pos = self.next_position(0);
+        dir.files = dir.list()?;
+        // Empty source keeps position of files of directory.
+        let mut loaders: Vec<Box<dyn ConfigSource + Send + 'static>> =
+            vec![Box::new(HashSource::new(dir.name()))];
+        for file in dir.files.iter().rev() {
+            loaders.push(fragment(file)?);
+        }
+        self.insert_loaders(loaders, 0, group, pos)?;
+        self.dirs.push(dir);
+        Ok(self)
+    }
+
     /// Register random value source, must enable feature **rand**.
     ///
     /// Supported integer types:
@@ -322,44 +838,259 @@ impl Configuration {
 
     /// Register customized source, see [How to Initialize Configuration](index.html#how-to-initialize-configuration),
     /// [ConfigSource](source/trait.ConfigSource.html) for details.
+    ///
+    /// Source is registered with priority `0`, see [`Configuration::register_source_with_priority`].
     pub fn register_source<L: ConfigSource + 'static>(
+        self,
+        loader: L,
+    ) -> Result<Self, ConfigError> {
+        self.register_source_with_priority(loader, 0)
+    }
+
+    /// Register customized source with priority, see [`Stage`] for named priorities.
+    ///
+    /// Sources with higher priority override sources with lower priority,
+    /// sources with the same priority follow registration order, earlier registrations win.
+    ///
+    /// ```rust
+    /// use cfg_rs::{source::*, *};
+    /// struct Remote;
+    /// impl ConfigSource for Remote {
+    ///     fn name(&self) -> &str {
+    ///         "remote"
+    ///     }
+    ///     fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+    ///         builder.set("app.name", "remote");
+    ///         Ok(())
+    ///     }
+    /// }
+    /// let config = Configuration::new()
+    ///     .register_kv("file")
+    ///     .set("app.name", "file")
+    ///     .finish()
+    ///     .unwrap()
+    ///     .register_source_with_priority(Remote, Stage::Env)
+    ///     .unwrap();
+    /// assert_eq!("remote", config.get::<String>("app.name").unwrap());
+    /// assert_eq!(vec![("remote", 2000), ("file", 0)], config.sources());
+    /// ```
+    pub fn register_source_with_priority<L: ConfigSource + 'static, P: Into<i32>>(
         mut self,
         loader: L,
+        priority: P,
     ) -> Result<Self, ConfigError> {
-        if self.max <= self.loaders.len() {
+        let priority = priority.into();
+        let pos = self.next_position(priority);
+        self.insert_source(loader, priority, pos)?;
+        Ok(self)
+    }
+
+    /// Insert source at `pos` of sources with the same priority.
+    pub(crate) fn insert_source<L: ConfigSource + 'static>(
+        &mut self,
+        loader: L,
+        priority: i32,
+        pos: usize,
+    ) -> Result<(), ConfigError> {
+        self.insert_loaders(
+            vec![Box::new(CacheConfigSource::new(loader))],
+            priority,
+            None,
+            pos,
+        )
+    }
+
+    /// Insert sources of a file and files imported by it at `pos`, keys of imports are marked as used.
+    pub(crate) fn insert_imports(
+        &mut self,
+        imports: Imports,
+        priority: i32,
+        pos: usize,
+    ) -> Result<(), ConfigError> {
+        self.insert_loaders(imports.loaders, priority, None, pos)?;
+        for key in imports.keys {
+            self.source.mark_used(&key);
+        }
+        Ok(())
+    }
+
+    /// Insert sources at `pos` and load them once, `group` is id of directory of the sources.
+    fn insert_loaders(
+        &mut self,
+        loaders: Vec<Box<dyn ConfigSource + Send + 'static>>,
+        priority: i32,
+        group: Option<usize>,
+        pos: usize,
+    ) -> Result<(), ConfigError> {
+        let len = loaders.len();
+        if self.max < self.loaders.len() + len {
             return Err(ConfigError::TooManyInstances(self.max));
         }
-        let loader = CacheConfigSource::new(loader);
-        let builder = &mut self.source.prefixed();
-        loader.load(builder)?;
-        cfg_log!(
-            log::Level::Debug,
-            "Config source {}:{} registered.",
-            self.loaders.len() + 1,
-            loader.name()
-        );
-        self.loaders.push(Box::new(loader));
+        let last = pos == self.loaders.len();
+        self.loaders
+            .splice(pos..pos, loaders.into_iter().map(|l| (priority, group, l)));
+        if last {
+            for (_, _, loader) in &self.loaders[pos..] {
+                loader.load(&mut self.source.next_layer(loader.name()))?;
+            }
+        } else {
+            // Values of higher priority must be loaded first, so rebuild all.
+            self.rebuild()?;
+        }
+        #[cfg(feature = "log")]
+        for (i, (_, _, loader)) in self.loaders[pos..pos + len].iter().enumerate() {
+            cfg_log!(
+                log::Level::Debug,
+                "Config source {}:{} registered with priority {}.",
+                pos + i + 1,
+                loader.name(),
+                priority
+            );
+        }
+        Ok(())
+    }
+
+    /// Position of next source registered with `priority`.
+    pub(crate) fn next_position(&self, priority: i32) -> usize {
+        self.loaders.partition_point(|(p, _, _)| *p >= priority)
+    }
+
+    /// Set how arrays under key `prefix` are merged across sources, default is [`ArrayMerge::Replace`].
+    ///
+    /// The longest matching prefix wins, use `""` to change strategy of all arrays.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .set_array_merge("app.plugins", ArrayMerge::Append)
+    ///     .unwrap()
+    ///     .register_kv("prod")
+    ///     .set("app.hosts[0]", "x")
+    ///     .set("app.plugins[0]", "metrics")
+    ///     .finish()
+    ///     .unwrap()
+    ///     .register_kv("base")
+    ///     .set("app.hosts[0]", "a")
+    ///     .set("app.hosts[1]", "b")
+    ///     .set("app.plugins[0]", "log")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(vec!["x"], config.get::<Vec<String>>("app.hosts").unwrap());
+    /// assert_eq!(vec!["metrics", "log"], config.get::<Vec<String>>("app.plugins").unwrap());
+    /// ```
+    pub fn set_array_merge<K: Into<String>>(
+        mut self,
+        prefix: K,
+        merge: ArrayMerge,
+    ) -> Result<Self, ConfigError> {
+        let prefix = prefix.into();
+        self.source.merge.retain(|(p, _)| *p != prefix);
+        self.source.merge.push((prefix, merge));
+        if !self.loaders.is_empty() {
+            self.rebuild()?;
+        }
         Ok(self)
     }
 
+    fn new_source(&self) -> HashSource {
+        let mut source = HashSource::new("configuration");
+        source.policy = self.source.policy;
+        source.merge = self.source.merge.clone();
+        source.resolvers = self.source.resolvers.clone();
+        source.expressions = self.source.expressions;
+        source
+    }
+
+    fn rebuild(&mut self) -> Result<(), ConfigError> {
+        let mut source = self.new_source();
+        for (_, _, l) in &self.loaders {
+            l.load(&mut source.next_layer(l.name()))?;
+        }
+        self.source.replace_values(source);
+        Ok(())
+    }
+
+    /// Position of the empty source of directory `group`, files of it follow in reverse order.
+    fn group_position(&self, group: usize) -> usize {
+        self.loaders
+            .iter()
+            .position(|(_, g, _)| *g == Some(group))
+            .unwrap_or_default()
+    }
+
+    /// Position of the empty source of directory of `change`, and sources of its current files in reverse order,
+    /// sources of registered files are reused.
+    fn fragments<'a>(
+        &'a self,
+        change: &'a DirChange,
+    ) -> (usize, Vec<&'a (dyn ConfigSource + Send + 'static)>) {
+        let dir = &self.dirs[change.group];
+        let pos = self.group_position(change.group);
+        let mut v = vec![];
+        for file in change.files.iter().rev() {
+            v.push(match dir.files.iter().position(|f| f == file) {
+                Some(i) => self.loaders[pos + dir.files.len() - i].2.as_ref(),
+                None => change
+                    .added
+                    .iter()
+                    .find(|(f, _)| f == file)
+                    .map(|(_, l)| l.as_ref())
+                    .expect("NP"),
+            });
+        }
+        (pos, v)
+    }
+
+    /// Load all sources, files of directories are loaded by current files of them.
     #[inline]
-    fn reload(&self) -> Result<(bool, Configuration), ConfigError> {
+    fn reload(&self) -> Result<(bool, Configuration, Vec<DirChange>), ConfigError> {
         let mut s = Configuration::new();
+        s.source = self.new_source();
         let mut refreshed = false;
-        for i in self.loaders.iter() {
+        for (_, _, i) in self.loaders.iter() {
             if i.refreshable()? {
                 refreshed = true;
             }
         }
-        if refreshed {
-            let c = &mut s.source.prefixed();
-            for i in self.loaders.iter() {
-                i.load(c)?;
+        let mut changes = vec![];
+        let mut len = self.loaders.len();
+        for (group, dir) in self.dirs.iter().enumerate() {
+            let files = dir.list()?;
+            if files != dir.files {
+                len = len + files.len() - dir.files.len();
+                if self.max < len {
+                    return Err(ConfigError::TooManyInstances(self.max));
+                }
+                let mut added = vec![];
+                for file in files.iter().filter(|f| !dir.files.contains(f)) {
+                    added.push((file.clone(), fragment(file)?));
+                }
+                changes.push(DirChange {
+                    group,
+                    files,
+                    added,
+                });
+            }
+        }
+        if refreshed || !changes.is_empty() {
+            let fragments: Vec<_> = changes.iter().map(|c| self.fragments(c)).collect();
+            for (pos, (_, group, i)) in self.loaders.iter().enumerate() {
+                match fragments.iter().find(|(p, _)| *p == pos) {
+                    // Current files of changed directory are loaded after its empty source.
+                    Some((_, v)) => {
+                        i.load(&mut s.source.next_layer(i.name()))?;
+                        for l in v {
+                            l.load(&mut s.source.next_layer(l.name()))?;
+                        }
+                    }
+                    None if group.is_some_and(|g| changes.iter().any(|c| c.group == g)) => {}
+                    None => i.load(&mut s.source.next_layer(i.name()))?,
+                }
             }
             self.source.refs.refresh(&s)?;
             cfg_log!(log::Level::Info, "Configuration refreshed");
         }
-        Ok((refreshed, s))
+        Ok((refreshed || !changes.is_empty(), s, changes))
     }
 
     /// Refresh all [RefValue](struct.RefValue.html)s without change [`Configuration`] itself.
@@ -368,10 +1099,43 @@ impl Configuration {
     }
 
     /// Refresh all [RefValue](struct.RefValue.html)s and [`Configuration`] itself.
+    ///
+    /// Files added to or removed from directories of [`Configuration::register_dir`] are registered or removed,
+    /// [`ConfigError::TooManyInstances`] is returned if sources are more than the limit.
     pub fn refresh(&mut self) -> Result<bool, ConfigError> {
-        let (x, c) = self.reload()?;
+        let (x, c, changes) = self.reload()?;
+        for DirChange {
+            group,
+            files,
+            mut added,
+        } in changes
+        {
+            let pos = self.group_position(group);
+            let priority = self.loaders[pos].0;
+            let mut old: Vec<_> = self
+                .loaders
+                .drain(pos + 1..pos + 1 + self.dirs[group].files.len())
+                .map(|(_, _, l)| Some(l))
+                .collect();
+            // Registered files are in reverse order.
+            old.reverse();
+            let dir = &mut self.dirs[group];
+            let mut v = vec![];
+            for file in files.iter().rev() {
+                let loader = match dir.files.iter().position(|f| f == file) {
+                    Some(i) => old[i].take().expect("NP"),
+                    None => {
+                        let i = added.iter().position(|(f, _)| f == file).expect("NP");
+                        added.swap_remove(i).1
+                    }
+                };
+                v.push((priority, Some(group), loader));
+            }
+            dir.files = files;
+            self.loaders.splice(pos + 1..pos + 1, v);
+        }
         if x {
-            self.source.value = c.source.value;
+            self.source.replace_values(c.source);
         }
         Ok(x)
     }
@@ -391,6 +1155,170 @@ impl Configuration {
             let mut context = self.source.new_context(cache);
             context.parse_config(key, None)
         })
+        .map_err(|e| self.suggest(e))
+    }
+
+    /// Add [`Configuration::suggestions`] to context of [`ConfigError::ConfigNotFound`].
+    fn suggest(&self, e: ConfigError) -> ConfigError {
+        let suggestions = match e.kind() {
+            ConfigError::ConfigNotFound(key) => self.suggestions(key),
+            _ => return e,
+        };
+        e.with_context(|c| c.suggestions = suggestions)
+    }
+
+    /// Sibling keys and env variables similar to missing `key`, nearest first.
+    ///
+    /// It is used to find typos of [`ConfigError::ConfigNotFound`].
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_kv("kv")
+    ///     .set("postgresql.hots", "localhost")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(vec!["postgresql.hots"], config.suggestions("postgresql.host"));
+    /// let err = config.get::<String>("postgresql.host").unwrap_err();
+    /// assert_eq!(["postgresql.hots"], err.suggestions());
+    /// ```
+    pub fn suggestions(&self, key: &str) -> Vec<String> {
+        let mut suggestions = vec![];
+        if !key.ends_with(']') {
+            let (parent, name) = match key.rfind('.') {
+                Some(pos) => (&key[..pos], &key[pos + 1..]),
+                None => ("", key),
+            };
+            let siblings = CacheString::with_key(|cache| {
+                let mut context = self.source.new_context(cache);
+                context.track = false;
+                let mark = context.key.push(parent);
+                let keys = context.collect_keys();
+                context.key.pop(mark);
+                Ok(keys
+                    .str_key
+                    .into_iter()
+                    .map(|k| k.to_string())
+                    .collect::<Vec<_>>())
+            })
+            .unwrap_or_default();
+            for s in similar(name, siblings.iter().map(String::as_str)) {
+                suggestions.push(match parent {
+                    "" => s.to_string(),
+                    p => format!("{}.{}", p, s),
+                });
+            }
+        }
+        if !self.env_prefix.is_empty() {
+            let name: String = key
+                .chars()
+                .filter(|c| *c != ']')
+                .map(|c| match c {
+                    '.' | '[' => '_',
+                    c => c.to_ascii_uppercase(),
+                })
+                .collect();
+            let vars: Vec<String> = var_names()
+                .filter(|k| self.env_prefix.iter().any(|p| k.starts_with(p.as_str())))
+                .collect();
+            for p in &self.env_prefix {
+                let target = format!("{}{}", p, name);
+                for s in similar(&target, vars.iter().map(String::as_str)) {
+                    if !suggestions.iter().any(|v| v == s) {
+                        suggestions.push(s.to_string());
+                    }
+                }
+            }
+        }
+        suggestions.truncate(3);
+        suggestions
+    }
+
+    /// Get config like [`Configuration::get`], but keys are not marked as used.
+    fn get_untracked<T: FromConfig>(&self, key: &str) -> Result<T, ConfigError> {
+        CacheString::with_key(|cache| {
+            let mut context = self.source.new_context(cache);
+            context.track = false;
+            context.parse_config(key, None)
+        })
+    }
+
+    /// Get keys which have values but are never read, with names of sources supplying them, sorted by key.
+    ///
+    /// Keys read by [`Configuration::get`] and its variants, including placeholders expanded, are used.
+    /// [`Configuration::export`] and [`Configuration::explain`] do not mark keys as used.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_kv("kv")
+    ///     .set("app.port", 8080)
+    ///     .set("app.prot", 8081)
+    ///     .finish()
+    ///     .unwrap();
+    /// let port: u16 = config.get("app.port").unwrap();
+    /// assert_eq!(vec![("app.prot", "kv")], config.unused_keys("app"));
+    /// ```
+    ///
+    /// * `prefix` - Only keys under prefix are checked, use `""` for all keys.
+    pub fn unused_keys(&self, prefix: &str) -> Vec<(&str, &str)> {
+        self.source
+            .entries()
+            .into_iter()
+            .map(|(k, _)| k)
+            .filter(|k| is_under(k, prefix) && !self.source.is_used(k))
+            .map(|k| (k, self.source.origin_of(k).map_or("", |(s, _)| s)))
+            .collect()
+    }
+
+    /// Check there are no [unused keys](Configuration::unused_keys) under `prefix`, otherwise return
+    /// [`ConfigError::ConfigCause`] caused by [`UnusedKeys`].
+    ///
+    /// * `prefix` - Only keys under prefix are checked, use `""` for all keys.
+    pub fn check_unused(&self, prefix: &str) -> Result<(), ConfigError> {
+        let keys = self.unused_keys(prefix);
+        if keys.is_empty() {
+            return Ok(());
+        }
+        Err(ConfigError::from_cause(crate::UnusedKeys(
+            keys.into_iter().map(|(k, _)| k.to_string()).collect(),
+        )))
+    }
+
+    /// Get config by key with [`serde::Deserialize`], must enable feature **serde**.
+    ///
+    /// Placeholder resolution and value coercion are the same as [`Configuration::get`],
+    /// sequences are read from index keys and maps from string keys.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "serde")]
+    /// # {
+    /// use cfg_rs::*;
+    /// #[derive(serde::Deserialize)]
+    /// struct Server {
+    ///     host: String,
+    ///     port: u16,
+    /// }
+    /// let config = Configuration::new()
+    ///     .register_kv("inline")
+    ///     .set("server.host", "localhost")
+    ///     .set("server.port", "8080")
+    ///     .finish()
+    ///     .unwrap();
+    /// let server: Server = config.get_serde("server").unwrap();
+    /// assert_eq!(8080, server.port);
+    /// # }
+    /// ```
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    pub fn get_serde<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<T, ConfigError> {
+        CacheString::with_key(|cache| {
+            let mut context = self.source.new_context(cache);
+            context.do_parse_with(key, None, &mut HashSet::new(), |context, value| {
+                crate::de::deserialize_here(context, value, std::marker::PhantomData::<T>)
+            })
+        })
+        .map_err(|e| self.suggest(e))
     }
 
     /// Get config from configuration by key, otherwise return default. See [`ConfigKey`] for the key's pattern details.
@@ -410,18 +1338,217 @@ impl Configuration {
 
     /// Get source names, just for test.
     pub fn source_names(&self) -> Vec<&str> {
-        self.loaders.iter().map(|l| l.name()).collect()
+        self.loaders.iter().map(|(_, _, l)| l.name()).collect()
     }
 
-    /// Create predefined sources builder, see [init](struct.PredefinedConfigurationBuilder.html#method.init) for details.
-    pub fn with_predefined_builder() -> PredefinedConfigurationBuilder {
-        PredefinedConfigurationBuilder {
-            memory: HashSource::new("fixed:FromProgram/CommandLineArgs"),
-            cargo: None,
-            prefix: None,
-            init: None,
+    /// Explain where value of `key` comes from, see [`Provenance`] for details.
+    ///
+    /// Values of keys matching [redact patterns](Configuration::set_redact_patterns) are replaced by `***`,
+    /// as [`Configuration::export`] does.
+    ///
+    /// * `key` - Config key.
+    pub fn explain(&self, key: &str) -> Provenance {
+        let (resolved, mut expansions) = with_trace(|| self.get_untracked::<Option<String>>(key));
+        let mut definitions = CacheString::with_key(|cache| {
+            let mut context = self.source.new_context(cache);
+            let mark = context.key.push(key);
+            let v = self.source.definitions(&context.key);
+            context.key.pop(mark);
+            Ok(v)
+        })
+        .unwrap_or_default();
+        let mut resolved = resolved.ok().flatten();
+        let secret = self.is_redacted(key);
+        if secret {
+            resolved = resolved.map(|_| "***".to_string());
+            for d in &mut definitions {
+                d.value = ConfigValue::Str("***".to_string());
+            }
         }
-    }
+        for (k, v) in &mut expansions {
+            if secret || self.is_redacted(k) {
+                *v = "***".to_string();
+            }
+        }
+        Provenance {
+            key: key.to_string(),
+            definitions,
+            resolved,
+            expansions,
+        }
+    }
+
+    /// Get all keys with values, sorted, placeholders are not expanded.
+    pub fn keys(&self) -> Vec<&str> {
+        self.iter().map(|(k, _)| k).collect()
+    }
+
+    /// Iterate raw values of all keys, sorted by key, placeholders are not expanded.
+    ///
+    /// Please use [`Configuration::get`] or [`Configuration::export`] for resolved values.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ConfigValue<'static>)> {
+        self.source.entries().into_iter()
+    }
+
+    /// Get resolved config tree under `prefix`, use `""` for all configs.
+    ///
+    /// * `prefix` - Config key prefix.
+    pub fn subtree(&self, prefix: &str) -> Result<ConfigTree, ConfigError> {
+        Ok(self
+            .get::<Option<ConfigTree>>(prefix)?
+            .unwrap_or_else(|| ConfigTree::Map(Default::default())))
+    }
+
+    /// Set patterns of keys whose values are redacted in [`Configuration::export`] and debug output,
+    /// `*` matches any characters and matching is case insensitive.
+    /// Default patterns are `*password*`, `*secret*` and `*token*`.
+    ///
+    /// * `patterns` - Key patterns, such as `*password*` or `app.db.*`.
+    pub fn set_redact_patterns<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        patterns: I,
+    ) -> Self {
+        self.redact = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub(crate) fn is_redacted(&self, key: &str) -> bool {
+        self.redact.iter().any(|p| glob_match(p, key))
+    }
+
+    /// Export effective configuration with placeholders expanded, and values of keys matching
+    /// [redact patterns](Configuration::set_redact_patterns) replaced by `***`.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_kv("kv")
+    ///     .set("app.name", "demo")
+    ///     .set("app.url", "http://${app.name}")
+    ///     .set("app.db.password", "pa55")
+    ///     .finish()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     "app.db.password=***\napp.name=demo\napp.url=http://demo\n",
+    ///     config.export(Format::Properties).unwrap()
+    /// );
+    /// ```
+    ///
+    /// * `format` - Export format.
+    pub fn export(&self, format: Format) -> Result<String, ConfigError> {
+        let tree = redact(
+            self.get_untracked::<Option<ConfigTree>>("")?
+                .unwrap_or_else(|| ConfigTree::Map(Default::default())),
+            "",
+            &self.redact,
+        );
+        export(&tree, format)
+    }
+
+    /// Render text template, placeholders are expanded the same way as values, such as `${key}` and `${key:default}`.
+    /// `$` and `}` out of placeholders are kept as they are, and `\` escapes the next char, such as `\${key}`.
+    ///
+    /// Blocks between `${#for item in key}` and `${/for}` repeat for each item of array or map under `key`,
+    /// `${item}` and `${item.sub_key}` in the block read the current item. `${#for index, item in key}` also
+    /// binds `${index}` to index of array or name of map, map items are sorted by name.
+    /// Loop tags standing on their own lines are removed with the lines.
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_kv("kv")
+    ///     .set("app.port", 8080)
+    ///     .set("app.servers[0]", "10.0.0.1")
+    ///     .set("app.servers[1]", "10.0.0.2")
+    ///     .finish()
+    ///     .unwrap();
+    /// let template = "upstream app {
+    ///     ${#for s in app.servers}
+    ///     server ${s}:${app.port};
+    ///     ${/for}
+    /// }
+    /// ";
+    /// assert_eq!(
+    ///     "upstream app {\n    server 10.0.0.1:8080;\n    server 10.0.0.2:8080;\n}\n",
+    ///     config.render(template).unwrap()
+    /// );
+    /// ```
+    pub fn render(&self, template: &str) -> Result<String, ConfigError> {
+        let nodes = parse_template(template)?;
+        let mut out = String::with_capacity(template.len());
+        render_template(
+            &nodes,
+            &mut vec![],
+            &mut |text| {
+                CacheString::with_key(|cache| {
+                    let context = self.source.new_context(cache);
+                    Ok(
+                        match ConfigContext::parse_placeholder(
+                            &self.source,
+                            &context.key,
+                            text,
+                            &mut HashSet::new(),
+                            true,
+                        )? {
+                            (false, Some(ConfigValue::Str(v))) => v,
+                            (false, Some(ConfigValue::StrRef(v))) => v.to_string(),
+                            _ => text.to_string(),
+                        },
+                    )
+                })
+            },
+            &mut |key| {
+                CacheString::with_key(|cache| {
+                    let mut context = self.source.new_context(cache);
+                    let mark = context.key.push(key);
+                    let c = context.collect_keys();
+                    context.key.pop(mark);
+                    let mut names: Vec<&str> = c.str_key.into_iter().collect();
+                    names.sort_unstable();
+                    let sep = if key.is_empty() { "" } else { "." };
+                    Ok((0..c.int_key.unwrap_or(0))
+                        .map(|i| (i.to_string(), format!("{}[{}]", key, i)))
+                        .chain(
+                            names
+                                .into_iter()
+                                .map(|n| (n.to_string(), format!("{}{}{}", key, sep, n))),
+                        )
+                        .collect())
+                })
+            },
+            &mut out,
+        )?;
+        Ok(out)
+    }
+
+    /// Render text template file, see [`Configuration::render`].
+    pub fn render_file<P: Into<PathBuf>>(&self, path: P) -> Result<String, ConfigError> {
+        let path = path.into();
+        let name = format!("template:{}", path.display());
+        std::fs::read_to_string(&path)
+            .map_err(ConfigError::from_cause)
+            .and_then(|template| self.render(&template))
+            .map_err(|e| e.with_file(&name, &path))
+    }
+
+    /// Get source names with their effective priorities, from highest to lowest.
+    pub fn sources(&self) -> Vec<(&str, i32)> {
+        self.loaders
+            .iter()
+            .map(|(p, _, l)| (l.name(), *p))
+            .collect()
+    }
+
+    /// Create predefined sources builder, see [init](struct.PredefinedConfigurationBuilder.html#method.init) for details.
+    pub fn with_predefined_builder() -> PredefinedConfigurationBuilder {
+        PredefinedConfigurationBuilder {
+            memory: HashSource::new("fixed:FromProgram/CommandLineArgs"),
+            cargo: None,
+            prefix: None,
+            null_marker: None,
+            init: None,
+        }
+    }
 
     /// Create predefined configuration, see [init](struct.PredefinedConfigurationBuilder.html#method.init) for details.
     pub fn with_predefined() -> Result<Self, ConfigError> {
@@ -435,6 +1562,7 @@ pub struct PredefinedConfigurationBuilder {
     memory: HashSource,
     cargo: Option<Cargo>,
     prefix: Option<String>,
+    null_marker: Option<String>,
     init: Option<Box<dyn FnOnce(&Configuration) -> Result<(), ConfigError> + 'static>>,
 }
 
@@ -455,6 +1583,14 @@ impl PredefinedConfigurationBuilder {
         self
     }
 
+    /// Set null marker of environment variables, see [`Configuration::register_prefix_env_with_null`].
+    ///
+    /// Variables with value `marker` remove the key and its sub keys set by files.
+    pub fn set_env_null_marker<K: ToString>(mut self, marker: K) -> Self {
+        self.null_marker = Some(marker.to_string());
+        self
+    }
+
     /// Set config file directory.
     pub fn set_dir<V: Into<PathBuf>>(self, path: V) -> Self {
         self.set("app.dir", path.into().display().to_string())
@@ -507,18 +1643,28 @@ impl PredefinedConfigurationBuilder {
     ///
     /// ## Predefined Sources.
     ///
-    /// 0. Cargo Package Env Variables (Must be explicitly set by [set_cargo_env](struct.PredefinedConfigurationBuilder.html#method.set_cargo_env)).
-    /// 1. Customized by Programming or Commandline Args.[^f_default]
-    /// 2. Random Value (Auto enabled with feature `rand`).
-    /// 3. Environment Variable with Prefix `CFG`, referto [set_prefix_env](struct.PredefinedConfigurationBuilder.html#method.set_prefix_env) for details.[^f_default]
-    /// 4. Profiled File Source with Path, `${app.dir}/${app.name}-${app.profile}.EXT`. EXT: toml, json, yaml.[^f_file]
-    /// 5. File Source with Path, `${app.dir}/${app.name}.EXT`. EXT: toml, json, yaml.[^f_file]
-    /// 6. Customized Source Can be Registered by [register_source](struct.Configuration.html#method.register_source).
+    /// 0. Cargo Package Env Variables (Must be explicitly set by [set_cargo_env](struct.PredefinedConfigurationBuilder.html#method.set_cargo_env)). Stage: [`Stage::Overrides`].
+    /// 1. Customized by Programming or Commandline Args.[^f_default] Stage: [`Stage::Overrides`].
+    /// 2. Random Value (Auto enabled with feature `rand`). Stage: [`Stage::Env`].
+    /// 3. Environment Variable with Prefix `CFG`, referto [set_prefix_env](struct.PredefinedConfigurationBuilder.html#method.set_prefix_env) for details.[^f_default] Stage: [`Stage::Env`].
+    /// 4. Profiled File Source with Path, `${app.dir}/${app.name}-${app.profile}.EXT`. EXT: toml, json, yaml.[^f_file] Stage: [`Stage::Files`].
+    ///    Multiple profiles are separated by comma, such as `app.profile = "prod,eu-west,canary"`,
+    ///    one file is loaded per profile, and later profiles take precedence.
+    ///    Profile sections in `${app.dir}/${app.name}.EXT` are loaded after the profile file of the same profile,
+    ///    such as `[_profiles.prod]` in toml, or yaml documents declaring `_profile: prod`.[^f_profile]
+    /// 5. File Source with Path, `${app.dir}/${app.name}.EXT`. EXT: toml, json, yaml.[^f_file] Stage: [`Stage::Files`].
+    /// 6. Customized Source Can be Registered by [register_source](struct.Configuration.html#method.register_source),
+    ///    or inserted between predefined sources by [register_source_with_priority](struct.Configuration.html#method.register_source_with_priority).
     ///
     /// [^f_default]: Always be enabled.
     ///
     /// [^f_file]: See [Supported File Formats](index.html#supported-file-format) for details.
     ///
+    /// [^f_profile]: Supported by toml, yaml and json. Source names of profile sections show the profile, such as
+    ///     `file:app.[toml,tml] (profile prod)`. Profile sections are not loaded as keys of the file,
+    ///     neither by [register_file](struct.Configuration.html#method.register_file).
+    ///     The key is `_profiles` rather than `profile`, so that existing `profile.*` keys are kept.
+    ///
     /// ## Crate Feature
     ///
     /// * Feature `rand` to enable random value source.
@@ -530,18 +1676,19 @@ impl PredefinedConfigurationBuilder {
 
         // Layer 0, cargo dev envs.
         if let Some(cargo) = self.cargo {
-            config = config.register_source(cargo)?;
+            config = config.register_source_with_priority(cargo, Stage::Overrides)?;
         }
 
         // Layer 1, commandlines.
-        config = config.register_source(self.memory)?;
+        config = config.register_source_with_priority(self.memory, Stage::Overrides)?;
 
         let option: SourceOption = config.get_predefined()?;
 
         // Layer 2, random
         #[cfg(feature = "rand")]
         if option.random.enabled {
-            config = config.register_random()?;
+            config =
+                config.register_source_with_priority(crate::source::random::Random, Stage::Env)?;
         }
 
         // Layer 3, environment.
@@ -550,28 +1697,61 @@ impl PredefinedConfigurationBuilder {
             .or_else(|| config.get::<Option<String>>("env.prefix").ok().flatten())
             .or_else(|| var("CFG_ENV_PREFIX").ok())
             .unwrap_or_else(|| "CFG".to_owned());
-        config = config.register_prefix_env(&prefix)?;
+        let mut env = PrefixEnvironment::new(&prefix);
+        if let Some(marker) = &self.null_marker {
+            env = env.with_null(marker);
+        }
+        config = config.register_env(env, Stage::Env.into())?;
 
         if let Some(init) = self.init {
             (init)(&config)?;
             cfg_log!(log::Level::Info, "Early initialization completed.");
         }
 
-        // Layer 4, profile file.
+        // Layer 4, profile files and profile sections, later profiles take precedence.
         let app = config.get_predefined::<AppConfig>()?;
         let mut path = PathBuf::new();
         if let Some(d) = app.dir {
             path.push(d);
         };
-        if let Some(profile) = &app.profile {
-            let mut path = path.clone();
-            path.push(format!("{}-{}", app.name, profile));
-            config = register_files(config, &option, path, false)?;
+        let profiles: Vec<&str> = app
+            .profile
+            .as_deref()
+            .map(|v| split_profile_names(v).collect())
+            .unwrap_or_default();
+        for profile in profiles.iter().rev() {
+            let mut file = path.clone();
+            file.push(format!("{}-{}", app.name, profile));
+            config = register_files(
+                config,
+                &option,
+                file,
+                false,
+                Stage::Files.into(),
+                &Section::Base,
+            )?;
+            let mut file = path.clone();
+            file.push(&app.name);
+            config = register_files(
+                config,
+                &option,
+                file,
+                false,
+                Stage::Files.into(),
+                &Section::Profile(profile.to_string()),
+            )?;
         }
 
         // Layer 5, file.
         path.push(app.name);
-        config = register_files(config, &option, path, false)?;
+        config = register_files(
+            config,
+            &option,
+            path,
+            false,
+            Stage::Files.into(),
+            &Section::Base,
+        )?;
 
         cfg_log!(
             log::Level::Info,
@@ -597,10 +1777,20 @@ pub struct ManualSource(Configuration, HashSource);
 impl ManualSource {
     /// Set config into configuration by programming, or from command line arguments.
     pub fn set<K: Borrow<str>, V: Into<ConfigValue<'static>>>(mut self, key: K, value: V) -> Self {
-        self.0.source = self.0.source.set(key, value);
+        self.1 = self.1.set(key, value);
         self
     }
 
+    /// Set [`ToConfig`] instance into configuration by programming.
+    pub fn set_config<K: Borrow<str>, V: ToConfig + ?Sized>(
+        mut self,
+        key: K,
+        value: &V,
+    ) -> Result<Self, ConfigError> {
+        self.1.prefixed().set_config(key.borrow(), value)?;
+        Ok(self)
+    }
+
     /// Finish customized kv.
     pub fn finish(self) -> Result<Configuration, ConfigError> {
         self.0.register_source(self.1)
@@ -618,7 +1808,8 @@ mod test {
     macro_rules! should_eq {
         ($context:ident: $val:literal as $t:ty = $x:expr  ) => {
             println!("{} key: {}", type_name::<$t>(), $val);
-            assert_eq!($x, &format!("{:?}", $context.get::<$t>($val)));
+            let v = $context.get::<$t>($val);
+            assert_eq!($x, &format!("{:?}", v.as_ref().map_err(ConfigError::kind)));
         };
     }
 
@@ -728,6 +1919,8 @@ mod test {
     #[test]
     fn get_test() {
         let config = build_config()
+            .set_array_merge("key", ArrayMerge::MergeByIndex)
+            .unwrap()
             .register_kv("k1")
             .set("key[0]", "xx")
             .finish()
@@ -853,7 +2046,10 @@ mod test {
         let app_cfg = ConfigContext {
             key: CacheString::new().new_key(),
             source: &src,
+            base_policy: CoercionPolicy::Default,
+            policy: CoercionPolicy::Default,
             ref_value_flag: false,
+            track: true,
         }
         .parse_config::<AppConfig>("app", None)
         .unwrap();
@@ -867,7 +2063,10 @@ mod test {
         let app_cfg = ConfigContext {
             key: CacheString::new().new_key(),
             source: &src2,
+            base_policy: CoercionPolicy::Default,
+            policy: CoercionPolicy::Default,
             ref_value_flag: false,
+            track: true,
         }
         .parse_config::<AppConfig>("app", None)
         .unwrap();
@@ -876,4 +2075,1149 @@ mod test {
         assert_eq!(app_cfg.dir, None);
         assert_eq!(app_cfg.profile, None);
     }
+
+    #[test]
+    fn priority_test() {
+        let kv = |name: &str, value: &str| {
+            let mut source = HashSource::new(name);
+            source.prefixed().set("key", value.to_string());
+            source.prefixed().set(name, value.to_string());
+            source
+        };
+        let config = Configuration::new()
+            .register_source(kv("plain", "plain"))
+            .unwrap()
+            .register_source_with_priority(kv("defaults", "defaults"), Stage::Defaults)
+            .unwrap()
+            .register_source_with_priority(kv("files", "files"), Stage::Files)
+            .unwrap()
+            .register_source_with_priority(kv("env", "env"), Stage::Env)
+            .unwrap()
+            .register_source_with_priority(kv("env2", "env2"), Stage::Env)
+            .unwrap()
+            .register_source_with_priority(kv("custom", "custom"), 1500)
+            .unwrap();
+        assert_eq!(
+            vec![
+                ("env", 2000),
+                ("env2", 2000),
+                ("custom", 1500),
+                ("files", 1000),
+                ("plain", 0),
+                ("defaults", -1000)
+            ],
+            config.sources()
+        );
+        assert_eq!("env", config.get::<String>("key").unwrap());
+        for name in ["plain", "defaults", "files", "env", "env2", "custom"] {
+            assert_eq!(name, config.get::<String>(name).unwrap());
+        }
+    }
+
+    #[test]
+    fn priority_insert_test() {
+        let config = Configuration::with_predefined_builder()
+            .set("app.name", "no_such_app")
+            .init()
+            .unwrap()
+            .register_kv("remote")
+            .set("remote.key", "remote")
+            .finish()
+            .unwrap();
+        assert_eq!(Some(&("remote", 0)), config.sources().last());
+
+        let config = Configuration::with_predefined_builder()
+            .set("app.name", "no_such_app")
+            .set("app.key", "memory")
+            .init()
+            .unwrap()
+            .register_source_with_priority(
+                HashSource::new("remote")
+                    .set("app.key", "remote")
+                    .set("remote.key", "remote"),
+                Stage::Env as i32 - 1,
+            )
+            .unwrap();
+        let sources = config.sources();
+        let pos = sources.iter().position(|(n, _)| *n == "remote").unwrap();
+        assert!(sources[..pos].iter().all(|(_, p)| *p >= Stage::Env.into()));
+        assert!(
+            sources[pos + 1..]
+                .iter()
+                .all(|(_, p)| *p <= Stage::Files.into())
+        );
+        assert_eq!("memory", config.get::<String>("app.key").unwrap());
+        assert_eq!("remote", config.get::<String>("remote.key").unwrap());
+        assert_eq!("no_such_app", config.get::<String>("app.name").unwrap());
+    }
+
+    #[test]
+    fn array_merge_test() {
+        let layers = |config: Configuration| {
+            config
+                .register_kv("high")
+                .set("a[0]", "x")
+                .set("b.c[0]", "x")
+                .set("d[0].name", "x")
+                .finish()
+                .unwrap()
+                .register_kv("low")
+                .set("a[0]", "a")
+                .set("a[1]", "b")
+                .set("a[2]", "c")
+                .set("b.c[0]", "a")
+                .set("b.c[1]", "b")
+                .set("d[0].name", "a")
+                .set("d[0].port", "1")
+                .set("d[1].name", "b")
+                .finish()
+                .unwrap()
+        };
+        let get = |config: &Configuration, key: &str| config.get::<Vec<String>>(key).unwrap();
+
+        let config = layers(Configuration::new());
+        assert_eq!(vec!["x"], get(&config, "a"));
+        assert_eq!(vec!["x"], get(&config, "b.c"));
+        assert_eq!("x", config.get::<String>("d[0].name").unwrap());
+        assert_eq!(None, config.get::<Option<String>>("d[1].name").unwrap());
+        assert_eq!(None, config.get::<Option<u16>>("d[0].port").unwrap());
+
+        let config = layers(
+            Configuration::new()
+                .set_array_merge("", ArrayMerge::Append)
+                .unwrap()
+                .set_array_merge("b", ArrayMerge::MergeByIndex)
+                .unwrap(),
+        );
+        assert_eq!(vec!["x", "a", "b", "c"], get(&config, "a"));
+        assert_eq!(vec!["x", "b"], get(&config, "b.c"));
+        assert_eq!("a", config.get::<String>("d[1].name").unwrap());
+        assert_eq!(1, config.get::<u16>("d[1].port").unwrap());
+        assert_eq!("b", config.get::<String>("d[2].name").unwrap());
+
+        // Strategy can be changed after sources registered.
+        let config = config.set_array_merge("", ArrayMerge::Replace).unwrap();
+        assert_eq!(vec!["x"], get(&config, "a"));
+        assert_eq!(vec!["x", "b"], get(&config, "b.c"));
+
+        // Arrays follow priorities rather than registration order.
+        let config = Configuration::new()
+            .register_kv("low")
+            .set("a[0]", "a")
+            .set("a[1]", "b")
+            .finish()
+            .unwrap()
+            .register_source_with_priority(HashSource::new("high").set("a[0]", "x"), Stage::Env)
+            .unwrap();
+        assert_eq!(vec!["x"], get(&config, "a"));
+    }
+
+    #[test]
+    fn explain_test() {
+        let config = Configuration::new()
+            .register_kv("file")
+            .set("app.port", 80)
+            .set("app.host", "localhost")
+            .set("app.url", "http://${app.host}:${app.port}")
+            .finish()
+            .unwrap()
+            .register_source_with_priority(HashSource::new("env").set("app.port", 8080), Stage::Env)
+            .unwrap();
+        let p = config.explain("app.port");
+        assert_eq!("app.port", p.key);
+        assert_eq!(
+            vec!["env", "file"],
+            p.definitions
+                .iter()
+                .map(|d| d.source.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(p.winner().unwrap().value, ConfigValue::Int(8080)));
+        assert!(matches!(p.definitions[1].value, ConfigValue::Int(80)));
+        assert!(p.winner().unwrap().location.is_none());
+        assert_eq!(Some("8080"), p.resolved.as_deref());
+        assert!(p.expansions.is_empty());
+
+        let p = config.explain("app.url");
+        assert_eq!(Some("http://localhost:8080"), p.resolved.as_deref());
+        assert_eq!(
+            vec![
+                ("app.host".to_string(), "localhost".to_string()),
+                ("app.port".to_string(), "8080".to_string())
+            ],
+            p.expansions
+        );
+        assert_eq!(
+            "app.url = http://localhost:8080\n  * file: Str(\"http://${app.host}:${app.port}\")\n  ${app.host} => localhost\n  ${app.port} => 8080\n",
+            p.to_string()
+        );
+
+        let config = config
+            .register_kv("secret")
+            .set("db.password", "hunter2")
+            .set("db.url", "pg://admin:${db.password}@localhost")
+            .finish()
+            .unwrap();
+        let p = config.explain("db.password");
+        assert_eq!(
+            "db.password = ***\n  * secret: Str(\"***\")\n",
+            p.to_string()
+        );
+        let p = config.explain("db.url");
+        assert_eq!(
+            vec![("db.password".to_string(), "***".to_string())],
+            p.expansions
+        );
+
+        let p = config.explain("app.none");
+        assert!(p.winner().is_none());
+        assert_eq!(None, p.resolved);
+        assert_eq!("app.none is not resolved\n", p.to_string());
+
+        // Explaining does not leak expansions into later reads.
+        assert_eq!(
+            "http://localhost:8080",
+            config.get::<String>("app.url").unwrap()
+        );
+    }
+
+    fn export_config() -> Configuration {
+        Configuration::new()
+            .register_kv("kv")
+            .set("app.name", "demo")
+            .set("app.url", "http://${app.name}:${app.port}")
+            .set("app.port", 8080)
+            .set("app.hosts[0]", "a")
+            .set("app.hosts[1]", "b")
+            .set("app.db[0].user", "root")
+            .set("app.db[0].password", "pa55")
+            .set("app.api_token", "t0k")
+            .set("app.removed", ConfigValue::Null)
+            .finish()
+            .unwrap()
+    }
+
+    #[test]
+    fn keys_test() {
+        let config = export_config();
+        let keys = config.keys();
+        assert!(keys.contains(&"app.name"));
+        assert!(keys.contains(&"app.hosts[1]"));
+        assert!(!keys.contains(&"app.removed"));
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(sorted, keys);
+        let (_, v) = config.iter().find(|(k, _)| *k == "app.url").unwrap();
+        assert!(matches!(v, ConfigValue::Str(v) if v == "http://${app.name}:${app.port}"));
+        assert_eq!(
+            "http://demo:8080",
+            config
+                .subtree("app.url")
+                .unwrap()
+                .to_string()
+                .trim_matches('"')
+        );
+        match config.subtree("app.hosts").unwrap() {
+            ConfigTree::Array(v) => assert_eq!(2, v.len()),
+            _ => panic!("not array"),
+        }
+        assert!(matches!(config.subtree("none").unwrap(), ConfigTree::Map(v) if v.is_empty()));
+        let debug = format!("{:?}", config);
+        assert!(debug.contains("\"kv\""));
+        assert!(debug.contains("\"app.db[0].password\": \"***\""));
+        assert!(!debug.contains("pa55"));
+    }
+
+    #[test]
+    fn export_test() {
+        let config = export_config();
+        assert_eq!(
+            "app.api_token=***
+app.db[0].password=***
+app.db[0].user=root
+app.hosts[0]=a
+app.hosts[1]=b
+app.name=demo
+app.port=8080
+app.url=http://demo:8080
+",
+            config.export(Format::Properties).unwrap()
+        );
+        assert_eq!(
+            "APP_API_TOKEN=\"***\"
+APP_DB_0_PASSWORD=\"***\"
+APP_DB_0_USER=\"root\"
+APP_HOSTS_0=\"a\"
+APP_HOSTS_1=\"b\"
+APP_NAME=\"demo\"
+APP_PORT=\"8080\"
+APP_URL=\"http://demo:8080\"
+",
+            config.export(Format::Env).unwrap()
+        );
+        let e = Configuration::new()
+            .register_kv("kv")
+            .set("app.db-url", "a")
+            .set("app.db_url", "b")
+            .finish()
+            .unwrap()
+            .export(Format::Env)
+            .unwrap_err();
+        assert!(
+            e.to_string()
+                .contains("env name APP_DB_URL is also exported by key app.db-url"),
+            "{}",
+            e
+        );
+        assert_eq!(
+            r#""app":
+  "api_token": "***"
+  "db":
+    - "password": "***"
+      "user": "root"
+  "hosts":
+    - "a"
+    - "b"
+  "name": "demo"
+  "port": 8080
+  "url": "http://demo:8080"
+"#,
+            config.export(Format::Yaml).unwrap()
+        );
+        assert_eq!(
+            r#"
+[app]
+api_token = "***"
+hosts = ["a", "b"]
+name = "demo"
+port = 8080
+url = "http://demo:8080"
+
+[[app.db]]
+password = "***"
+user = "root"
+"#,
+            config.export(Format::Toml).unwrap()
+        );
+        let json = config.export(Format::Json).unwrap();
+        assert!(json.contains(r#""password":"***""#));
+        assert!(json.contains(r#""url":"http://demo:8080""#));
+
+        let config = config.set_redact_patterns(["app.db*"]);
+        let props = config.export(Format::Properties).unwrap();
+        assert!(props.contains("app.db=***\n"));
+        assert!(props.contains("app.api_token=t0k\n"));
+    }
+
+    #[test]
+    fn export_non_finite_float_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("a", f64::NAN)
+            .set("b", f64::INFINITY)
+            .set("c", f64::NEG_INFINITY)
+            .set("d[0]", f64::NAN)
+            .set("d[1]", 0.5)
+            .finish()
+            .unwrap();
+        assert_eq!(
+            "a = nan\nb = inf\nc = -inf\nd = [nan, 0.5]\n",
+            config.export(Format::Toml).unwrap()
+        );
+    }
+
+    #[test]
+    fn glob_match_test() {
+        assert!(glob_match("*password*", "app.db.Password"));
+        assert!(glob_match("*secret", "app.secret"));
+        assert!(!glob_match("*secret", "app.secret.key"));
+        assert!(glob_match("app.*.key", "app.x.y.key"));
+        assert!(glob_match("app", "APP"));
+        assert!(!glob_match("app", "app.x"));
+        assert!(glob_match("*", "app"));
+    }
+
+    #[test]
+    fn unused_keys_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("app.host", "localhost")
+            .set("app.url", "http://${app.host}")
+            .set("app.prot", 8080)
+            .set("app.db.password", "pa55")
+            .set("app.map.a", 1)
+            .set("app.map.b", 2)
+            .set("other", "x")
+            .finish()
+            .unwrap()
+            .register_source_with_priority(HashSource::new("env").set("app.hots", "h"), Stage::Env)
+            .unwrap();
+        assert_eq!(8, config.unused_keys("").len());
+        let _ = config.export(Format::Json).unwrap();
+        let _ = config.explain("app.url");
+        assert_eq!(8, config.unused_keys("").len());
+
+        assert_eq!("http://localhost", config.get::<String>("app.url").unwrap());
+        let _ = config
+            .get::<std::collections::HashMap<String, u8>>("app.map")
+            .unwrap();
+        assert_eq!(None, config.get::<Option<String>>("app.port").unwrap());
+        assert_eq!(
+            vec![
+                ("app.db.password", "kv"),
+                ("app.hots", "env"),
+                ("app.prot", "kv")
+            ],
+            config.unused_keys("app")
+        );
+        assert_eq!(vec![("other", "kv")], config.unused_keys("other"));
+        assert!(config.unused_keys("app.map").is_empty());
+        assert!(config.unused_keys("ap").is_empty());
+        assert!(config.check_unused("app.map").is_ok());
+        let e = config.check_unused("app").unwrap_err();
+        match std::error::Error::source(&e).and_then(|e| e.downcast_ref::<crate::UnusedKeys>()) {
+            Some(keys) => assert_eq!(vec!["app.db.password", "app.hots", "app.prot"], keys.0),
+            _ => panic!("unused keys not detected"),
+        }
+
+        // Used keys are kept after sources changed.
+        let config = config.set_array_merge("app", ArrayMerge::Append).unwrap();
+        assert_eq!(3, config.unused_keys("app").len());
+    }
+
+    #[test]
+    fn suggest_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("postgresql.hots", "localhost")
+            .set("postgresql.port", 5432)
+            .set("app", 1)
+            .finish()
+            .unwrap();
+        match config
+            .get::<String>("postgresql.host")
+            .as_ref()
+            .map_err(ConfigError::kind)
+        {
+            Err(ConfigError::ConfigNotFound(key)) => {
+                assert_eq!("postgresql.host", key);
+                assert_eq!(vec!["postgresql.hots"], config.suggestions(key));
+            }
+            e => panic!("unexpected: {:?}", e),
+        }
+        let e = config.get::<String>("postgresql.host").unwrap_err();
+        assert_eq!(["postgresql.hots"], e.suggestions());
+        assert_eq!(
+            "Configuration not found: postgresql.host, did you mean postgresql.hots?",
+            e.to_string()
+        );
+        assert_eq!(vec!["app"], config.suggestions("apq"));
+        assert!(config.suggestions("postgresql.user").is_empty());
+        let e = config.get::<String>("postgresql.user").unwrap_err();
+        assert!(e.suggestions().is_empty());
+        assert_eq!("Configuration not found: postgresql.user", e.to_string());
+        assert_eq!(
+            None,
+            config.get::<Option<String>>("postgresql.host").unwrap()
+        );
+
+        unsafe { std::env::set_var("SUGGEST_TEST_POSTGRES_HOST", "localhost") };
+        let config = config.register_prefix_env("SUGGEST_TEST").unwrap();
+        assert_eq!(
+            vec!["postgresql.hots", "SUGGEST_TEST_POSTGRES_HOST"],
+            config.suggestions("postgresql.host")
+        );
+        assert_eq!(
+            ["postgresql.hots", "SUGGEST_TEST_POSTGRES_HOST"],
+            config
+                .get::<String>("postgresql.host")
+                .unwrap_err()
+                .suggestions()
+        );
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        use crate::err::edit_distance;
+        assert_eq!(0, edit_distance("host", "host"));
+        assert_eq!(1, edit_distance("host", "hots"));
+        assert_eq!(1, edit_distance("host", "hosts"));
+        assert_eq!(2, edit_distance("postgresql", "postgres"));
+        assert_eq!(3, edit_distance("", "abc"));
+        assert_eq!(
+            vec!["pork", "prot"],
+            similar("port", ["prot", "port", "pork", "host"])
+        );
+    }
+
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    #[allow(dead_code)]
+    struct PoolConfig {
+        max_size: u32,
+        enabled: Option<bool>,
+    }
+
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    #[allow(dead_code)]
+    struct PostgresConfig {
+        pool: PoolConfig,
+    }
+
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    #[allow(dead_code)]
+    struct ServiceConfig {
+        db: PostgresConfig,
+    }
+
+    #[test]
+    fn error_context_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("app.db.pool.max_size", "abc")
+            .finish()
+            .unwrap();
+        let e = config.get::<ServiceConfig>("app").unwrap_err();
+        assert!(matches!(e, ConfigError::ConfigCause(_)));
+        let c = e.context().unwrap();
+        assert_eq!(Some("app.db.pool.max_size"), c.key.as_deref());
+        assert_eq!(Some("kv"), c.source.as_deref());
+        assert_eq!(None, c.path);
+        assert_eq!(4, c.frames.len());
+        assert_eq!(
+            "ServiceConfig.db -> PostgresConfig.pool -> PoolConfig.max_size",
+            c.chain()
+        );
+        assert_eq!(
+            "Configuration error caused by: invalid digit found in string (key 'app.db.pool.max_size', source 'kv', in ServiceConfig.db -> PostgresConfig.pool -> PoolConfig.max_size)",
+            e.to_string()
+        );
+        assert!(matches!(e, ConfigError::ConfigCause(_)));
+
+        let config = config
+            .register_kv("ports")
+            .set("app.ports[0]", 80)
+            .set("app.ports[1]", "x")
+            .finish()
+            .unwrap();
+        let e = config.get::<Vec<u8>>("app.ports").unwrap_err();
+        assert_eq!(Some("app.ports[1]"), e.context().unwrap().key.as_deref());
+        assert_eq!("Vec<u8>[1]", e.context().unwrap().chain());
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn error_context_file_test() {
+        let path = PathBuf::from("target/error_context_test.toml");
+        std::fs::write(&path, "[app.db.pool]\nmax_size = 10\nenabled = \"maybe\"\n").unwrap();
+        let config = Configuration::new().register_file(&path, false).unwrap();
+        let e = config.get::<ServiceConfig>("app").unwrap_err();
+        assert!(matches!(
+            e.kind(),
+            ConfigError::ConfigParseError(k, v) if k == "app.db.pool.enabled" && v == "maybe"
+        ));
+        let c = e.context().unwrap();
+        assert_eq!(Some("app.db.pool.enabled"), c.key.as_deref());
+        assert_eq!(Some(path.as_path()), c.path.as_deref());
+        assert_eq!((Some(3), Some(1)), (c.line, c.column));
+        assert_eq!(
+            "ServiceConfig.db -> PostgresConfig.pool -> PoolConfig.enabled",
+            c.chain()
+        );
+        assert_eq!(
+            format!(
+                "Configuration parse error for key 'app.db.pool.enabled': maybe (source '{}', at {}:3:1, in {})",
+                c.source.as_deref().unwrap(),
+                path.display(),
+                c.chain()
+            ),
+            e.to_string()
+        );
+
+        std::fs::write(&path, "[app.db.pool]\nmax_size = true\n").unwrap();
+        let config = Configuration::new()
+            .register_file(&path, false)
+            .unwrap()
+            .set_coercion_policy(CoercionPolicy::Strict);
+        let e = config.get::<ServiceConfig>("app").unwrap_err();
+        assert!(matches!(
+            e.kind(),
+            ConfigError::ConfigTypeMismatch(k, "Bool", "u32") if k == "app.db.pool.max_size"
+        ));
+        let c = e.context().unwrap();
+        assert_eq!(Some("app.db.pool.max_size"), c.key.as_deref());
+        assert_eq!(Some(path.as_path()), c.path.as_deref());
+        assert_eq!((Some(2), Some(1)), (c.line, c.column));
+        assert_eq!(
+            "ServiceConfig.db -> PostgresConfig.pool -> PoolConfig.max_size",
+            c.chain()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn resolver_test() {
+        unsafe { std::env::set_var("RESOLVER_TEST_HOME", "/home/test") };
+        std::fs::write("target/resolver_test.txt", " pa55\n").unwrap();
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("app.fallback", "${file:fallback}")
+            .set("app.name", "demo")
+            .set("app.home", "${env:RESOLVER_TEST_HOME}/app")
+            .set("app.password", "${file:target/resolver_test.txt}")
+            .set("app.token", "${base64:dG9rZW4=}")
+            .set("app.title", "${upper:${app.name}}")
+            .set("app.missing", "${env:RESOLVER_TEST_MISSING}")
+            .set("app.default", "${app.none:fallback}")
+            .set("app.self", "${upper:${app.self}}")
+            .finish()
+            .unwrap();
+        // Built-in resolvers are opt-in, `${file:fallback}` is key `file` with default value.
+        assert_eq!("fallback", config.get::<String>("app.fallback").unwrap());
+        assert_eq!("dG9rZW4=", config.get::<String>("app.token").unwrap());
+
+        let config = config
+            .register_builtin_resolvers()
+            .register_resolver("upper", |v: &str| Ok(v.to_uppercase()));
+        assert_eq!("/home/test/app", config.get::<String>("app.home").unwrap());
+        assert_eq!("pa55", config.get::<String>("app.password").unwrap());
+        assert_eq!("token", config.get::<String>("app.token").unwrap());
+        assert_eq!("DEMO", config.get::<String>("app.title").unwrap());
+        assert_eq!("fallback", config.get::<String>("app.default").unwrap());
+        assert!(matches!(
+            config.get::<String>("app.missing").as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveNotFound(k)) if k == "env:RESOLVER_TEST_MISSING"
+        ));
+        assert!(matches!(
+            config
+                .get::<String>("app.self")
+                .as_ref()
+                .map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveError(_))
+        ));
+        let p = config.explain("app.title");
+        assert_eq!(
+            vec![
+                ("app.name".to_string(), "demo".to_string()),
+                ("upper:demo".to_string(), "DEMO".to_string())
+            ],
+            p.expansions
+        );
+
+        assert!(matches!(
+            config.get::<String>("app.fallback").as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigParseError(k, _)) if k == "file:fallback"
+        ));
+
+        let config = config.register_resolver("upper", |v: &str| Ok(format!("<{}>", v)));
+        assert_eq!("<demo>", config.get::<String>("app.title").unwrap());
+        std::fs::remove_file("target/resolver_test.txt").unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn env_null_marker_test() {
+        let dir = PathBuf::from("target/env_null_marker_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.toml"), "[db]\nurl = \"x\"\nuser = \"root\"\n").unwrap();
+        unsafe { std::env::set_var("ENV_NULL_MARKER_TEST_DB_URL", "null") };
+        let config = Configuration::with_predefined_builder()
+            .set_dir(dir.clone())
+            .set_prefix_env("ENV_NULL_MARKER_TEST")
+            .set_env_null_marker("null")
+            .init()
+            .unwrap();
+        assert_eq!(None, config.get::<Option<String>>("db.url").unwrap());
+        assert_eq!("root", config.get::<String>("db.user").unwrap());
+
+        let config = Configuration::with_predefined_builder()
+            .set_dir(dir.clone())
+            .set_prefix_env("ENV_NULL_MARKER_TEST")
+            .init()
+            .unwrap();
+        assert_eq!("null", config.get::<String>("db.url").unwrap());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn profiles_test() {
+        let dir = PathBuf::from("target/profiles_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("app.toml"),
+            "a = \"base\"\nb = \"base\"\nc = \"base\"\nd = \"base\"\n\n[_profiles.prod]\nb = \"prod-section\"\nc = \"prod-section\"\n\n[_profiles.canary]\nd = \"canary-section\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("app-prod.toml"), "c = \"prod\"\nd = \"prod\"\n").unwrap();
+        std::fs::write(dir.join("app-canary.toml"), "e = \"canary\"\n").unwrap();
+        let config = Configuration::with_predefined_builder()
+            .set_dir(dir.clone())
+            .set_profile("prod, eu-west,canary")
+            .set_prefix_env("PROFILES_TEST")
+            .init()
+            .unwrap();
+        assert_eq!("base", config.get::<String>("a").unwrap());
+        assert_eq!("prod-section", config.get::<String>("b").unwrap());
+        assert_eq!("prod", config.get::<String>("c").unwrap());
+        assert_eq!("canary-section", config.get::<String>("d").unwrap());
+        assert_eq!("canary", config.get::<String>("e").unwrap());
+        assert_eq!(
+            None,
+            config.get::<Option<String>>("_profiles.prod.b").unwrap()
+        );
+
+        let p = config.explain("b");
+        assert_eq!(
+            "file:target/profiles_test/app.[toml,tml] (profile prod)",
+            p.definitions[0].source
+        );
+        assert_eq!(
+            Some(7),
+            p.definitions[0].location.as_ref().and_then(|l| l.line)
+        );
+        assert_eq!(
+            "file:target/profiles_test/app.[toml,tml]",
+            p.definitions[1].source
+        );
+        let names: Vec<&str> = config
+            .sources()
+            .into_iter()
+            .map(|(n, _)| n)
+            .filter(|n| n.contains(".[toml"))
+            .collect();
+        assert_eq!(
+            vec![
+                "file:target/profiles_test/app-canary.[toml,tml]",
+                "file:target/profiles_test/app.[toml,tml] (profile canary)",
+                "file:target/profiles_test/app-eu-west.[toml,tml]",
+                "file:target/profiles_test/app.[toml,tml] (profile eu-west)",
+                "file:target/profiles_test/app-prod.[toml,tml]",
+                "file:target/profiles_test/app.[toml,tml] (profile prod)",
+                "file:target/profiles_test/app.[toml,tml]",
+            ],
+            names
+        );
+
+        let config = Configuration::new()
+            .register_file(dir.join("app.toml"), true)
+            .unwrap();
+        assert_eq!("base", config.get::<String>("b").unwrap());
+        assert_eq!(
+            None,
+            config.get::<Option<String>>("_profiles.prod.b").unwrap()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn imports_test() {
+        let dir = PathBuf::from("target/imports_test");
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+        let write = |name: &str, content: &str| std::fs::write(dir.join(name), content).unwrap();
+        write(
+            "app.toml",
+            "a = \"app\"\n[app]\nimports = [\"db.toml\", \"optional:local.toml\", \"conf.d/*.toml\"]\n",
+        );
+        write("db.toml", "a = \"db\"\ndb.host = \"db\"\n");
+        write("conf.d/10-x.toml", "x = \"10\"\ny = \"10\"\n");
+        write("conf.d/20-x.toml", "x = \"20\"\n");
+        write("conf.d/30-x.txt", "x = \"30\"\n");
+        let mut config = Configuration::new()
+            .register_file(dir.join("app.toml"), true)
+            .unwrap();
+        assert_eq!("app", config.get::<String>("a").unwrap());
+        assert_eq!("db", config.get::<String>("db.host").unwrap());
+        assert_eq!("20", config.get::<String>("x").unwrap());
+        assert_eq!("10", config.get::<String>("y").unwrap());
+        assert_eq!(
+            vec![
+                "file:target/imports_test/app.toml.[toml,tml]",
+                "file:target/imports_test/conf.d/20-x.toml.[toml,tml]",
+                "file:target/imports_test/conf.d/10-x.toml.[toml,tml]",
+                "file:target/imports_test/local.toml.[toml,tml]",
+                "file:target/imports_test/db.toml.[toml,tml]",
+            ],
+            config.source_names()
+        );
+        assert!(config.unused_keys("app").is_empty());
+
+        // Imported files refresh on their own.
+        let mut refreshed = false;
+        for _ in 0..100 {
+            write("local.toml", "db.host = \"local\"\n");
+            if config.refresh().unwrap() {
+                refreshed = true;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(refreshed);
+        assert_eq!("local", config.get::<String>("db.host").unwrap());
+
+        write(
+            "override.toml",
+            "a = \"override\"\napp.imports = [\"db.toml\"]\napp.imports_override = true\n",
+        );
+        let config = Configuration::new()
+            .register_file(dir.join("override.toml"), true)
+            .unwrap();
+        assert_eq!("db", config.get::<String>("a").unwrap());
+
+        // Files of unsupported formats matched by glob are skipped.
+        write("glob.toml", "app.imports = [\"conf.d/*\"]\n");
+        let config = Configuration::new()
+            .register_file(dir.join("glob.toml"), true)
+            .unwrap();
+        assert_eq!("20", config.get::<String>("x").unwrap());
+        assert_eq!(3, config.source_names().len());
+
+        write("missing.toml", "app.imports = [\"none.toml\"]\n");
+        assert!(matches!(
+            Configuration::new().register_file(dir.join("missing.toml"), true),
+            Err(ConfigError::ConfigFileNotExists(_))
+        ));
+
+        write("c1.toml", "app.imports = [\"c2.toml\"]\n");
+        write("c2.toml", "app.imports = [\"c1.toml\"]\n");
+        match Configuration::new().register_file(dir.join("c1.toml"), true) {
+            Err(ConfigError::ConfigRecursiveError(chain)) => {
+                assert_eq!(3, chain.split(" -> ").count());
+                assert!(chain.ends_with("c1.toml"));
+            }
+            v => panic!("unexpected {:?}", v.map(|_| ())),
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn register_dir_test() {
+        let dir = PathBuf::from("target/register_dir_test");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, content: &str| std::fs::write(dir.join(name), content).unwrap();
+        write("10-a.toml", "a = \"10\"\nb = \"10\"\nc = [1, 2]\n");
+        write("20-b.toml", "a = \"20\"\nc = [3]\n");
+        write("readme.md", "a = \"md\"\n");
+        let mut config = Configuration::new()
+            .register_dir(dir.clone(), "*.toml")
+            .unwrap();
+        assert_eq!("20", config.get::<String>("a").unwrap());
+        assert_eq!("10", config.get::<String>("b").unwrap());
+        assert_eq!(vec![3], config.get::<Vec<u8>>("c").unwrap());
+        assert_eq!(
+            vec![
+                "dir:target/register_dir_test/*.toml",
+                "file:target/register_dir_test/20-b.toml.[toml,tml]",
+                "file:target/register_dir_test/10-a.toml.[toml,tml]",
+            ],
+            config.source_names()
+        );
+        let sources: Vec<String> = config
+            .explain("a")
+            .definitions
+            .into_iter()
+            .map(|d| d.source)
+            .collect();
+        assert_eq!(
+            vec![
+                "file:target/register_dir_test/20-b.toml.[toml,tml]",
+                "file:target/register_dir_test/10-a.toml.[toml,tml]",
+            ],
+            sources
+        );
+        assert!(!config.refresh().unwrap());
+
+        write("30-c.toml", "a = \"30\"\n");
+        assert!(config.refresh().unwrap());
+        assert_eq!("30", config.get::<String>("a").unwrap());
+        assert_eq!(4, config.source_names().len());
+        assert!(!config.refresh().unwrap());
+
+        config.max = 4;
+        write("40-d.toml", "a = \"40\"\n");
+        assert!(matches!(
+            config.refresh(),
+            Err(ConfigError::TooManyInstances(4))
+        ));
+        assert_eq!("30", config.get::<String>("a").unwrap());
+        assert_eq!(4, config.source_names().len());
+        std::fs::remove_file(dir.join("40-d.toml")).unwrap();
+        config.max = 64;
+
+        std::fs::remove_file(dir.join("30-c.toml")).unwrap();
+        std::fs::remove_file(dir.join("20-b.toml")).unwrap();
+        assert!(config.refresh().unwrap());
+        assert_eq!("10", config.get::<String>("a").unwrap());
+        assert_eq!(vec![1, 2], config.get::<Vec<u8>>("c").unwrap());
+
+        let mut refreshed = false;
+        for _ in 0..100 {
+            write("10-a.toml", "a = \"11\"\n");
+            if config.refresh().unwrap() {
+                refreshed = true;
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(refreshed);
+        assert_eq!("11", config.get::<String>("a").unwrap());
+        assert_eq!(None, config.get::<Option<String>>("b").unwrap());
+        assert_eq!(
+            vec![
+                "dir:target/register_dir_test/*.toml",
+                "file:target/register_dir_test/10-a.toml.[toml,tml]",
+            ],
+            config.source_names()
+        );
+
+        // Files of unsupported formats are skipped.
+        let config = Configuration::new().register_dir(dir.clone(), "*").unwrap();
+        assert_eq!("11", config.get::<String>("a").unwrap());
+        assert_eq!(2, config.source_names().len());
+
+        let config = Configuration::new()
+            .register_dir(dir.join("none"), "*.toml")
+            .unwrap();
+        assert!(config.keys().is_empty());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_profiles_test() {
+        let dir = PathBuf::from("target/yaml_profiles_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("app.yaml"),
+            "a: base\nb: base\n_profiles:\n  dev:\n    c: dev-section\n---\n_profile: prod\na: prod\n---\n_profile: [dev, test]\nb: dev\n---\nc: base\n",
+        )
+        .unwrap();
+        let load = |profile: &str| {
+            Configuration::with_predefined_builder()
+                .set_dir(dir.clone())
+                .set_profile(profile)
+                .set_prefix_env("YAML_PROFILES_TEST")
+                .init()
+                .unwrap()
+        };
+        let config = load("prod");
+        assert_eq!("prod", config.get::<String>("a").unwrap());
+        assert_eq!("base", config.get::<String>("b").unwrap());
+        assert_eq!("base", config.get::<String>("c").unwrap());
+        assert_eq!(
+            "file:target/yaml_profiles_test/app.[yaml,yml] (profile prod)",
+            config.explain("a").definitions[0].source
+        );
+
+        let config = load("dev");
+        assert_eq!("base", config.get::<String>("a").unwrap());
+        assert_eq!("dev", config.get::<String>("b").unwrap());
+        assert_eq!("dev-section", config.get::<String>("c").unwrap());
+        assert_eq!(None, config.get::<Option<String>>("_profile").unwrap());
+
+        let config = Configuration::new()
+            .register_file(dir.join("app.yaml"), true)
+            .unwrap();
+        assert_eq!("base", config.get::<String>("a").unwrap());
+        assert_eq!("base", config.get::<String>("b").unwrap());
+        assert_eq!("base", config.get::<String>("c").unwrap());
+        assert_eq!(
+            None,
+            config.get::<Option<String>>("_profiles.dev.c").unwrap()
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn render_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("app.name", "demo")
+            .set("upstreams.api.servers[0].host", "10.0.0.1")
+            .set("upstreams.api.servers[0].port", 8080)
+            .set("upstreams.api.servers[1].host", "10.0.0.2")
+            .set("upstreams.web.servers[0].host", "10.0.1.1")
+            .set("upstreams.web.servers[0].port", 80)
+            .finish()
+            .unwrap();
+        let template = "# ${app.name} \\${not.expanded}
+${#for name, u in upstreams}
+upstream ${name} {
+    ${#for i, s in u.servers}
+    server ${s.host}:${s.port:80}; # ${i}
+    ${/for}
+}
+${/for}
+${#for s in missing}never${/for}";
+        assert_eq!(
+            "# demo ${not.expanded}
+upstream api {
+    server 10.0.0.1:8080; # 0
+    server 10.0.0.2:80; # 1
+}
+upstream web {
+    server 10.0.1.1:80; # 0
+}
+",
+            config.render(template).unwrap()
+        );
+        assert_eq!("a, b", config.render("a, b").unwrap());
+        assert!(matches!(
+            config.render("${app.none}").as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveNotFound(k)) if k == "app.none"
+        ));
+        assert!(matches!(
+            config
+                .render("${#for s in a}")
+                .as_ref()
+                .map_err(ConfigError::kind),
+            Err(ConfigError::ConfigParseError(..))
+        ));
+
+        let path = PathBuf::from("target/render_test.conf");
+        std::fs::write(&path, "name = ${app.name}\n").unwrap();
+        assert_eq!("name = demo\n", config.render_file(path.clone()).unwrap());
+        std::fs::write(&path, "name = ${app.none}\n").unwrap();
+        assert!(matches!(
+            config.render_file(path.clone()).as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveNotFound(k)) if k == "app.none"
+        ));
+        std::fs::remove_file(&path).unwrap();
+        assert!(config.render_file(path).is_err());
+    }
+
+    #[test]
+    fn expression_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("env", "prod")
+            .set("server.port", "8080")
+            .set("pool.max-size", 4)
+            .set("admin.port", "${server.port + 1}")
+            .set("pool.total", "${pool.max-size * 2}")
+            .set("log.level", "${env == 'prod' ? 'warn' : 'debug'}")
+            .set(
+                "app.url",
+                "http://${upper(env)}:${admin.port}/${app.path:none}",
+            )
+            .set("app.default", "${${app.missing:3} + 1}")
+            .set("app.fail", "${server.port / 0}")
+            .set("app.missing_key", "${app.none + 1}")
+            .set("app.self", "${app.self + 1}")
+            .set("app.version", "1.10")
+            .set("app.tag", "${app.version + '-beta'}")
+            .finish()
+            .unwrap();
+        assert!(matches!(
+            config.get::<u16>("admin.port").as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveNotFound(k)) if k == "server.port + 1"
+        ));
+
+        let config = config.set_expressions(true);
+        assert_eq!(8081, config.get::<u16>("admin.port").unwrap());
+        assert_eq!(8, config.get::<u8>("pool.total").unwrap());
+        assert_eq!("warn", config.get::<String>("log.level").unwrap());
+        assert_eq!(
+            "http://PROD:8081/none",
+            config.get::<String>("app.url").unwrap()
+        );
+        assert_eq!(4, config.get::<u8>("app.default").unwrap());
+        assert_eq!("1.10-beta", config.get::<String>("app.tag").unwrap());
+        assert_eq!(
+            "Configuration parse error for key 'app.fail': division by zero in `server.port / 0` (source 'kv')",
+            config.get::<u16>("app.fail").unwrap_err().to_string()
+        );
+        assert!(matches!(
+            config.get::<u16>("app.missing_key").as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveNotFound(k)) if k == "app.none"
+        ));
+        assert!(matches!(
+            config
+                .get::<u16>("app.self")
+                .as_ref()
+                .map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveError(_))
+        ));
+        assert_eq!(
+            vec![
+                ("server.port".to_string(), "8080".to_string()),
+                ("server.port + 1".to_string(), "8081".to_string())
+            ],
+            config.explain("admin.port").expansions
+        );
+    }
+
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    struct NodeConfig {
+        host: String,
+        port: u16,
+        pool: PoolConfig,
+        tags: Vec<String>,
+        labels: std::collections::HashMap<String, String>,
+    }
+
+    #[test]
+    fn reference_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("db.primary.host", "10.0.0.1")
+            .set("db.primary.port", 5432)
+            .set("db.primary.pool.max_size", 8)
+            .set("db.primary.tags[0]", "a")
+            .set("db.primary.tags[1]", "b")
+            .set("db.primary.labels.zone", "z1")
+            .set("db.primary.labels.role", "primary")
+            .set("db.replica", "${@db.primary}")
+            .set("db.replica.host", "10.0.0.2")
+            .set("db.replica.labels.role", "replica")
+            .set("db.backup", "${@db.replica}")
+            .set("db.backup.pool.max_size", 2)
+            .set("db.removed", "${@db.primary}")
+            .set("db.removed.host", ConfigValue::Null)
+            .set("db.port", "${@db.primary.port}")
+            .set("a", "${@b}")
+            .set("b.x", "${@a.x}")
+            .set("c", "${@c.d}")
+            .finish()
+            .unwrap();
+        let replica = config.get::<NodeConfig>("db.replica").unwrap();
+        assert_eq!("10.0.0.2", replica.host);
+        assert_eq!(5432, replica.port);
+        assert_eq!(8, replica.pool.max_size);
+        assert_eq!(vec!["a", "b"], replica.tags);
+        assert_eq!("z1", replica.labels["zone"]);
+        assert_eq!("replica", replica.labels["role"]);
+
+        let backup = config.get::<NodeConfig>("db.backup").unwrap();
+        assert_eq!("10.0.0.2", backup.host);
+        assert_eq!(2, backup.pool.max_size);
+        assert_eq!(5432, config.get::<u16>("db.port").unwrap());
+        assert_eq!(
+            None,
+            config.get::<Option<String>>("db.removed.host").unwrap()
+        );
+        assert_eq!("10.0.0.1", config.get::<String>("db.primary.host").unwrap());
+        assert_eq!(
+            vec![("db.primary.labels.role", "kv"), ("db.removed", "kv")],
+            config.unused_keys("db")
+        );
+
+        assert!(matches!(
+            config.get::<String>("a.x").as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveError(k)) if k == "a.x -> b.x -> a.x"
+        ));
+        assert!(matches!(
+            config
+                .get::<String>("c.e")
+                .as_ref()
+                .map_err(ConfigError::kind),
+            Err(ConfigError::ConfigRecursiveError(_))
+        ));
+    }
 }
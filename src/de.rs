@@ -0,0 +1,583 @@
+//! Serde deserializer bridge.
+use std::{collections::HashSet, fmt::Display};
+
+use serde::de::{
+    self, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+
+use crate::{ConfigContext, ConfigError, ConfigValue, FromConfig, key::PartialKeyIter};
+
+impl de::Error for ConfigError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConfigError::ConfigParseError(String::new(), msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        ConfigError::ConfigNotFound(field.to_string())
+    }
+}
+
+/// Serde deserializer at current key of [`ConfigContext`].
+pub(crate) struct ConfigDeserializer<'a, 'b, 'v> {
+    context: &'b mut ConfigContext<'a>,
+    value: Option<ConfigValue<'v>>,
+}
+
+impl<'a, 'b, 'v> ConfigDeserializer<'a, 'b, 'v> {
+    pub(crate) fn new(context: &'b mut ConfigContext<'a>, value: Option<ConfigValue<'v>>) -> Self {
+        Self { context, value }
+    }
+
+    fn parse<T: FromConfig>(self) -> Result<T, ConfigError> {
+        T::from_config(self.context, self.value)
+    }
+}
+
+/// Deserialize sub key, errors raised by serde are filled with current key.
+fn deserialize_sub<'a, 'de, K, S>(
+    context: &mut ConfigContext<'a>,
+    key: K,
+    seed: S,
+) -> Result<S::Value, ConfigError>
+where
+    K: Into<PartialKeyIter<'a>>,
+    S: DeserializeSeed<'de>,
+{
+    context.do_parse_with(key, None, &mut HashSet::new(), |context, value| {
+        deserialize_here(context, value, seed)
+    })
+}
+
+pub(crate) fn deserialize_here<'de, S: DeserializeSeed<'de>>(
+    context: &mut ConfigContext<'_>,
+    value: Option<ConfigValue<'_>>,
+    seed: S,
+) -> Result<S::Value, ConfigError> {
+    seed.deserialize(ConfigDeserializer::new(context, value))
+        .map_err(|e| match e {
+            ConfigError::ConfigParseError(k, msg) if k.is_empty() => {
+                ConfigError::ConfigParseError(context.current_key(), msg)
+            }
+            e => e,
+        })
+}
+
+macro_rules! deserialize_value {
+    ($($f:ident.$v:ident: $t:ty),+) => {$(
+        fn $f<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            visitor.$v(self.parse::<$t>()?)
+        }
+    )+};
+}
+
+impl<'de> de::Deserializer<'de> for ConfigDeserializer<'_, '_, '_> {
+    type Error = ConfigError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Some(ConfigValue::StrRef(v)) => visitor.visit_str(v),
+            Some(ConfigValue::Str(v)) => visitor.visit_string(v),
+            Some(ConfigValue::Int(v)) => visitor.visit_i64(v),
+            Some(ConfigValue::Float(v)) => visitor.visit_f64(v),
+            Some(ConfigValue::BigInt(v)) => match u64::try_from(v) {
+                Ok(v) => visitor.visit_u64(v),
+                Err(_) => visitor.visit_i128(v),
+            },
+            Some(ConfigValue::Decimal(v)) => {
+                visitor.visit_f64(v.parse().map_err(ConfigError::from_cause)?)
+            }
+            Some(ConfigValue::Bool(v)) => visitor.visit_bool(v),
+            Some(ConfigValue::Null) => visitor.visit_unit(),
+            #[cfg(feature = "rand")]
+            Some(ConfigValue::Rand(v)) => {
+                ConfigDeserializer::new(self.context, Some(v.normalize())).deserialize_any(visitor)
+            }
+            None => {
+                let list = self.context.collect_keys();
+                if !list.str_key.is_empty() {
+                    self.deserialize_map(visitor)
+                } else if list.int_key.is_some() {
+                    self.deserialize_seq(visitor)
+                } else {
+                    Err(self.context.not_found())
+                }
+            }
+        }
+    }
+
+    deserialize_value!(
+        deserialize_bool.visit_bool: bool,
+        deserialize_i8.visit_i8: i8,
+        deserialize_i16.visit_i16: i16,
+        deserialize_i32.visit_i32: i32,
+        deserialize_i64.visit_i64: i64,
+        deserialize_i128.visit_i128: i128,
+        deserialize_u8.visit_u8: u8,
+        deserialize_u16.visit_u16: u16,
+        deserialize_u32.visit_u32: u32,
+        deserialize_u64.visit_u64: u64,
+        deserialize_u128.visit_u128: u128,
+        deserialize_f32.visit_f32: f32,
+        deserialize_f64.visit_f64: f64,
+        deserialize_str.visit_string: String,
+        deserialize_string.visit_string: String,
+        deserialize_identifier.visit_string: String
+    );
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let v = String::from_config(self.context, self.value)?;
+        let mut chars = v.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(self.context.parse_error(&v)),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_byte_buf(self.parse::<String>()?.into_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_bytes(visitor)
+    }
+
+    /// Missing key is `None`, so serde defaults apply to absent fields.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        if self.value.is_some() || self.context.is_present() {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let len = self.context.collect_keys().int_key.unwrap_or(0);
+        visitor.visit_seq(SeqDeserializer {
+            context: self.context,
+            index: 0,
+            len,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut keys: Vec<&str> = self.context.collect_keys().str_key.into_iter().collect();
+        keys.sort_unstable();
+        visitor.visit_map(MapDeserializer {
+            context: self.context,
+            keys: keys.into_iter(),
+            current: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let mut keys = vec![];
+        for f in fields {
+            if self.context.contains_key(f) {
+                keys.push(*f);
+            }
+        }
+        let current = self.context.current_key();
+        visitor
+            .visit_map(MapDeserializer {
+                context: self.context,
+                keys: keys.into_iter(),
+                current: None,
+            })
+            .map_err(|e| {
+                e.map_kind(|e| match e {
+                    ConfigError::ConfigNotFound(f) if fields.contains(&f.as_str()) => {
+                        ConfigError::ConfigNotFound(if current.is_empty() {
+                            f
+                        } else {
+                            format!("{}.{}", current, f)
+                        })
+                    }
+                    e => e,
+                })
+            })
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if self.value.is_some() {
+            let variant = self.parse::<String>()?;
+            return visitor.visit_enum(EnumDeserializer {
+                context: None,
+                variant,
+            });
+        }
+        let keys = self.context.collect_keys().str_key;
+        if keys.len() != 1 {
+            return Err(self.context.parse_error("expect one enum variant"));
+        }
+        let variant = keys.into_iter().next().expect("NP");
+        visitor.visit_enum(EnumDeserializer {
+            context: Some((self.context, variant)),
+            variant: variant.to_string(),
+        })
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+}
+
+struct SeqDeserializer<'a, 'b> {
+    context: &'b mut ConfigContext<'a>,
+    index: usize,
+    len: usize,
+}
+
+impl<'de> SeqAccess<'de> for SeqDeserializer<'_, '_> {
+    type Error = ConfigError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        if self.index >= self.len {
+            return Ok(None);
+        }
+        let i = self.index;
+        self.index += 1;
+        deserialize_sub(self.context, i, seed).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.len - self.index)
+    }
+}
+
+struct MapDeserializer<'a, 'b> {
+    context: &'b mut ConfigContext<'a>,
+    keys: std::vec::IntoIter<&'a str>,
+    current: Option<&'a str>,
+}
+
+impl<'de> MapAccess<'de> for MapDeserializer<'_, '_> {
+    type Error = ConfigError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.keys.next() {
+            Some(k) => {
+                self.current = Some(k);
+                deserialize_here(self.context, Some(ConfigValue::StrRef(k)), seed).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.current.take() {
+            Some(k) => deserialize_sub(self.context, k, seed),
+            None => Err(self.context.parse_error("map value without key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.keys.len())
+    }
+}
+
+struct EnumDeserializer<'a, 'b> {
+    context: Option<(&'b mut ConfigContext<'a>, &'a str)>,
+    variant: String,
+}
+
+impl<'de, 'a, 'b> EnumAccess<'de> for EnumDeserializer<'a, 'b> {
+    type Error = ConfigError;
+    type Variant = VariantDeserializer<'a, 'b>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Self::Error> {
+        let v = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((v, VariantDeserializer(self.context)))
+    }
+}
+
+struct VariantDeserializer<'a, 'b>(Option<(&'b mut ConfigContext<'a>, &'a str)>);
+
+impl<'de> VariantAccess<'de> for VariantDeserializer<'_, '_> {
+    type Error = ConfigError;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, Self::Error> {
+        match self.0 {
+            Some((context, key)) => deserialize_sub(context, key, seed),
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"newtype variant",
+            )),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some((context, key)) => {
+                context.do_parse_with(key, None, &mut HashSet::new(), |context, value| {
+                    de::Deserializer::deserialize_seq(
+                        ConfigDeserializer::new(context, value),
+                        visitor,
+                    )
+                })
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"tuple variant",
+            )),
+        }
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Some((context, key)) => {
+                context.do_parse_with(key, None, &mut HashSet::new(), |context, value| {
+                    de::Deserializer::deserialize_struct(
+                        ConfigDeserializer::new(context, value),
+                        "",
+                        fields,
+                        visitor,
+                    )
+                })
+            }
+            None => Err(de::Error::invalid_type(
+                de::Unexpected::UnitVariant,
+                &"struct variant",
+            )),
+        }
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Deserialize;
+
+    use crate::{ConfigError, Configuration, source::memory::HashSource, test::TestConfigExt};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Server {
+        host: String,
+        port: u16,
+        timeout: Option<f64>,
+        #[serde(default)]
+        tags: Vec<String>,
+        mode: Mode,
+        db: Option<Db>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Db {
+        url: String,
+        pool: u8,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Mode {
+        Fast,
+        Slow,
+        Custom { level: u8 },
+        Named(String),
+    }
+
+    fn build_config() -> Configuration {
+        HashSource::new("test")
+            .set("app.host", "${app.name}.local")
+            .set("app.name", "demo")
+            .set("app.port", "8080")
+            .set("app.tags[0]", "a")
+            .set("app.tags[1]", "b")
+            .set("app.mode", "fast")
+            .set("app.db.url", "db://${app.host}")
+            .set("app.db.pool", 4)
+            .set("mode.slow", "slow")
+            .set("custom.custom.level", "3")
+            .set("named.named", "x")
+            .set("map.k1", "1")
+            .set("map.k2", "2")
+            .new_config()
+    }
+
+    #[test]
+    fn struct_test() {
+        let config = build_config();
+        let server: Server = config.get_serde("app").unwrap();
+        assert_eq!(
+            Server {
+                host: "demo.local".to_string(),
+                port: 8080,
+                timeout: None,
+                tags: vec!["a".to_string(), "b".to_string()],
+                mode: Mode::Fast,
+                db: Some(Db {
+                    url: "db://demo.local".to_string(),
+                    pool: 4,
+                }),
+            },
+            server
+        );
+    }
+
+    #[test]
+    fn enum_test() {
+        let config = build_config();
+        assert_eq!(Mode::Fast, config.get_serde::<Mode>("app.mode").unwrap());
+        assert_eq!(Mode::Slow, config.get_serde::<Mode>("mode").unwrap());
+        assert_eq!(
+            Mode::Custom { level: 3 },
+            config.get_serde::<Mode>("custom").unwrap()
+        );
+        assert_eq!(
+            Mode::Named("x".to_string()),
+            config.get_serde::<Mode>("named").unwrap()
+        );
+        match config
+            .get_serde::<Mode>("app.name")
+            .as_ref()
+            .map_err(ConfigError::kind)
+        {
+            Err(ConfigError::ConfigParseError(k, _)) => assert_eq!("app.name", k),
+            e => panic!("unexpected: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn collection_test() {
+        let config = build_config();
+        let map: HashMap<String, u8> = config.get_serde("map").unwrap();
+        assert_eq!(Some(&2), map.get("k2"));
+        let map: BTreeMap<String, String> = config.get_serde("map").unwrap();
+        assert_eq!(2, map.len());
+        let v: Vec<String> = config.get_serde("app.tags").unwrap();
+        assert_eq!(vec!["a", "b"], v);
+        let v: Vec<String> = config.get_serde("not_exist").unwrap();
+        assert!(v.is_empty());
+        let v: (String, String) = config.get_serde("app.tags").unwrap();
+        assert_eq!(("a".to_string(), "b".to_string()), v);
+        let v: Option<u8> = config.get_serde("not_exist").unwrap();
+        assert_eq!(None, v);
+    }
+
+    #[test]
+    fn error_test() {
+        let config = build_config();
+        match config
+            .get_serde::<Db>("app")
+            .as_ref()
+            .map_err(ConfigError::kind)
+        {
+            Err(ConfigError::ConfigNotFound(k)) => assert_eq!("app.url", k),
+            e => panic!("unexpected: {:?}", e),
+        }
+        assert!(matches!(
+            config.get_serde::<u8>("app.name"),
+            Err(ConfigError::ConfigCause(_))
+        ));
+        assert!(matches!(
+            config
+                .get_serde::<u8>("app.not_exist")
+                .as_ref()
+                .map_err(ConfigError::kind),
+            Err(ConfigError::ConfigNotFound(..))
+        ));
+    }
+
+    #[test]
+    fn same_as_from_config_test() {
+        let config = build_config();
+        assert_eq!(
+            config.get::<u16>("app.port").unwrap(),
+            config.get_serde::<u16>("app.port").unwrap()
+        );
+        assert_eq!(
+            config.get::<String>("app.db.url").unwrap(),
+            config.get_serde::<String>("app.db.url").unwrap()
+        );
+        assert_eq!(
+            config.get::<String>("app.db.pool").unwrap(),
+            config.get_serde::<String>("app.db.pool").unwrap()
+        );
+        for key in ["app.tags", "map"] {
+            assert_eq!(
+                config.get::<Option<Vec<String>>>(key).unwrap(),
+                config.get_serde::<Option<Vec<String>>>(key).unwrap()
+            );
+        }
+        assert_eq!(
+            Some(vec![]),
+            config.get::<Option<Vec<u8>>>("not_exist").unwrap()
+        );
+        assert_eq!(
+            None,
+            config.get_serde::<Option<Vec<u8>>>("not_exist").unwrap()
+        );
+    }
+}
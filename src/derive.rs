@@ -10,7 +10,7 @@ pub trait FromConfigWithPrefix: FromConfig {
 #[cfg(test)]
 mod test {
     use crate::{source::memory::HashSource, test::TestConfigExt, *};
-    #[derive(FromConfig, Debug, PartialEq, Eq)]
+    #[derive(FromConfig, ToConfig, Debug, PartialEq, Eq)]
     #[config(prefix = "app", crate = "crate")]
     pub(crate) struct ConfigObject {
         hello: String,
@@ -41,4 +41,62 @@ mod test {
         let object2: ConfigObject = config.get_predefined().unwrap();
         assert_eq!(object, object2);
     }
+
+    #[derive(FromConfig, ToConfig, Debug, PartialEq)]
+    #[config(crate = "crate", rename_all = "kebab-case")]
+    struct RenameObject {
+        max_size: u8,
+        #[config(name = "LEVEL")]
+        log_level: String,
+        #[config(skip)]
+        cache: Vec<u8>,
+        inner_list: Vec<ConfigObject>,
+    }
+
+    #[test]
+    fn derive_rename_skip_test() {
+        let config = HashSource::new("test")
+            .set("app.max-size", "1")
+            .set("app.LEVEL", "info")
+            .set("app.cache[0]", "1")
+            .set("app.inner-list[0].hello", "world")
+            .set("app.inner-list[0].count", "2")
+            .new_config();
+        let object: RenameObject = config.get("app").unwrap();
+        assert_eq!(1, object.max_size);
+        assert_eq!("info", object.log_level);
+        assert!(object.cache.is_empty());
+        assert_eq!(2, object.inner_list[0].count_rename);
+    }
+
+    #[test]
+    fn derive_to_config_test() {
+        let object = RenameObject {
+            max_size: 3,
+            log_level: "${debug}".to_string(),
+            cache: vec![1],
+            inner_list: vec![ConfigObject {
+                hello: "world".to_string(),
+                option: Some("x".to_string()),
+                list: vec!["a".to_string()],
+                count: 4,
+                count_rename: 4,
+                def: 5,
+            }],
+        };
+        let mut source = HashSource::new("test");
+        source.prefixed().set_config("app", &object).unwrap();
+        let config = source.new_config();
+        assert_eq!(3, config.get::<u8>("app.max-size").unwrap());
+        assert_eq!(4, config.get::<u8>("app.inner-list[0].count").unwrap());
+        assert!(config.get::<Option<u8>>("app.cache[0]").unwrap().is_none());
+        let copy: RenameObject = config.get("app").unwrap();
+        assert_eq!(
+            RenameObject {
+                cache: vec![],
+                ..object
+            },
+            copy
+        );
+    }
 }
@@ -0,0 +1,504 @@
+//! Diagnostic reports of config errors.
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crate::{
+    ConfigError, Configuration, UnusedKeys, configuration::DEFAULT_REDACT_PATTERNS,
+    export::glob_match,
+};
+
+/// Diagnostic report of config errors, renders errors with source snippets, must enable feature **diagnostics**.
+///
+/// ```text
+/// error: Configuration error caused by: invalid digit found in string
+///  --> app.toml:3:1
+///   |
+/// 3 | port = "abc"
+///   | ^ invalid value
+///   = key: app.port
+///   = source: file:app.[toml]
+///   = in: AppConfig.port
+/// ```
+///
+/// Source lines are read from files when the report is rendered, so they show current content of files.
+/// Lines of keys matching [redact patterns](Configuration::set_redact_patterns) are not shown.
+#[derive(Debug)]
+pub struct Report<'a> {
+    errors: Vec<&'a ConfigError>,
+    config: Option<&'a Configuration>,
+}
+
+impl<'a> Report<'a> {
+    /// Create report of errors.
+    pub fn new<I: IntoIterator<Item = &'a ConfigError>>(errors: I) -> Self {
+        Self {
+            errors: errors.into_iter().collect(),
+            config: None,
+        }
+    }
+
+    /// Add hints from configuration, such as [`Configuration::suggestions`] of missing keys.
+    pub fn with_config(mut self, config: &'a Configuration) -> Self {
+        self.config = Some(config);
+        self
+    }
+}
+
+impl ConfigError {
+    /// Render error as diagnostic report, see [`Report`].
+    pub fn report(&self) -> Report<'_> {
+        Report::new([self])
+    }
+}
+
+/// Keys of error returned by [`Configuration::check_unused`].
+fn unused_keys(e: &ConfigError) -> Option<&UnusedKeys> {
+    e.source().and_then(|e| e.downcast_ref::<UnusedKeys>())
+}
+
+/// Message, caret label and hint of error.
+fn describe(e: &ConfigError, config: Option<&Configuration>) -> (String, String, Option<String>) {
+    match e.kind() {
+        ConfigError::ConfigNotFound(key) => {
+            let suggestions = match (e.suggestions(), config) {
+                ([], Some(c)) => c.suggestions(key),
+                (s, _) => s.to_vec(),
+            };
+            (
+                e.message(),
+                "not found".to_string(),
+                Some(if suggestions.is_empty() {
+                    format!("add `{}` to a config source", key)
+                } else {
+                    format!("did you mean {}?", suggestions.join(" or "))
+                }),
+            )
+        }
+        ConfigError::ConfigTypeMismatch(_, found, expected) => (
+            e.message(),
+            format!("expected {}", expected),
+            Some(
+                match e
+                    .context()
+                    .and_then(|c| c.policy)
+                    .or(config.map(Configuration::coercion_policy))
+                {
+                    Some(policy) => format!(
+                        "change the value to {}, {} is rejected by {} coercion policy",
+                        expected, found, policy
+                    ),
+                    None => format!(
+                        "change the value to {}, found {}; or use a lenient coercion policy",
+                        expected, found
+                    ),
+                },
+            ),
+        ),
+        ConfigError::ConfigRecursiveNotFound(_) => (
+            e.message(),
+            "placeholder not resolved".to_string(),
+            Some("define the key or give a default value like `${key:default}`".to_string()),
+        ),
+        ConfigError::ConfigRecursiveError(_) => (
+            e.message(),
+            "placeholder refers to itself".to_string(),
+            Some("remove the cycle between placeholders".to_string()),
+        ),
+        ConfigError::ConfigFileNotExists(_) => (
+            e.message(),
+            "file not exists".to_string(),
+            Some("create the file or register it as optional".to_string()),
+        ),
+        ConfigError::ConfigFileNotSupported(_) => (
+            e.message(),
+            "format not supported".to_string(),
+            Some("enable the feature of the file format, such as `toml` or `yaml`".to_string()),
+        ),
+        ConfigError::ConfigCause(_) if unused_keys(e).is_some() => (
+            e.message(),
+            "not used".to_string(),
+            Some("remove these keys or fix their names".to_string()),
+        ),
+        ConfigError::ConfigCause(_) if e.context().is_some_and(|c| c.key.is_none()) => {
+            (e.message(), "syntax error".to_string(), None)
+        }
+        _ => (e.message(), "invalid value".to_string(), None),
+    }
+}
+
+/// Check if source line of error may show a secret, that is the error is redacted, or its key matches
+/// [redact patterns](Configuration::set_redact_patterns), default patterns are used without configuration.
+fn is_secret(e: &ConfigError, config: Option<&Configuration>) -> bool {
+    if matches!(e.kind(), ConfigError::ConfigParseError(_, msg) if msg == "***") {
+        return true;
+    }
+    match (e.context().and_then(|c| c.key.as_deref()), config) {
+        (Some(key), Some(config)) => config.is_redacted(key),
+        (Some(key), None) => DEFAULT_REDACT_PATTERNS.iter().any(|p| glob_match(p, key)),
+        _ => false,
+    }
+}
+
+/// Source line at `line`, starts from 1.
+fn snippet(path: &std::path::Path, line: usize) -> Option<String> {
+    let content = std::fs::read_to_string(path).ok()?;
+    content
+        .lines()
+        .nth(line.checked_sub(1)?)
+        .map(|v| v.to_string())
+}
+
+fn render(
+    e: &ConfigError,
+    config: Option<&Configuration>,
+    f: &mut Formatter<'_>,
+) -> std::fmt::Result {
+    let (message, label, hint) = describe(e, config);
+    writeln!(f, "error: {}", message)?;
+    let c = e.context();
+    let line = c.and_then(|c| c.line);
+    let width = line.map_or(1, |l| l.to_string().len());
+    let pad = " ".repeat(width);
+    if let Some(c) = c {
+        if let Some(path) = &c.path {
+            write!(f, "{}--> {}", pad, path.display())?;
+            if let Some(line) = c.line {
+                write!(f, ":{}", line)?;
+                if let Some(column) = c.column {
+                    write!(f, ":{}", column)?;
+                }
+            }
+            writeln!(f)?;
+            let text = line
+                .filter(|_| !is_secret(e, config))
+                .and_then(|l| snippet(path, l).map(|s| (l, s)));
+            if let Some((n, text)) = text {
+                writeln!(f, "{} |", pad)?;
+                writeln!(f, "{} | {}", n, text)?;
+                // Keep tabs so that caret is aligned.
+                let indent: String = text
+                    .chars()
+                    .take(c.column.unwrap_or(1).saturating_sub(1))
+                    .map(|c| if c == '\t' { '\t' } else { ' ' })
+                    .collect();
+                writeln!(f, "{} | {}^ {}", pad, indent, label)?;
+            }
+        }
+    }
+    if let Some(keys) = unused_keys(e) {
+        for key in &keys.0 {
+            writeln!(f, "{} = key: {}", pad, key)?;
+        }
+    }
+    if let Some(c) = c {
+        if let Some(key) = &c.key {
+            writeln!(f, "{} = key: {}", pad, key)?;
+        }
+        if let Some(source) = &c.source {
+            writeln!(f, "{} = source: {}", pad, source)?;
+        }
+        if c.frames.len() > 1 {
+            writeln!(f, "{} = in: {}", pad, c.chain())?;
+        }
+    }
+    if let Some(hint) = hint {
+        writeln!(f, "{} = help: {}", pad, hint)?;
+    }
+    Ok(())
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, e) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            render(e, self.config, f)?;
+        }
+        if self.errors.len() > 1 {
+            writeln!(f, "\nerror: {} configuration errors", self.errors.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{CoercionPolicy, Configuration, FromConfig};
+
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    #[allow(dead_code)]
+    struct Server {
+        host: String,
+        port: u16,
+    }
+
+    #[test]
+    fn report_test() {
+        let config = Configuration::new()
+            .register_kv("kv")
+            .set("server.host", "localhost")
+            .set("server.port", "abc")
+            .set("server.timeout", 5)
+            .finish()
+            .unwrap();
+        let e = config.get::<Server>("server").unwrap_err();
+        assert_eq!(
+            "error: Configuration error caused by: invalid digit found in string
+  = key: server.port
+  = source: kv
+  = in: Server.port
+",
+            e.report().to_string()
+        );
+
+        let e1 = config.get::<String>("server.hots").unwrap_err();
+        let e2 = config.check_unused("").unwrap_err();
+        assert_eq!(
+            "error: Configuration not found: server.hots
+  = key: server.hots
+  = help: did you mean server.host?
+
+error: Configuration error caused by: keys not used: server.timeout
+  = key: server.timeout
+  = help: remove these keys or fix their names
+
+error: 2 configuration errors
+",
+            Report::new([&e1, &e2]).with_config(&config).to_string()
+        );
+
+        let config = config.set_coercion_policy(CoercionPolicy::Strict);
+        let e = config.get::<u16>("server.host").unwrap_err();
+        assert_eq!(
+            "error: Configuration type mismatch for key 'server.host': expected u16, found String
+  = key: server.host
+  = source: kv
+  = help: change the value to u16, String is rejected by strict coercion policy
+",
+            e.report().with_config(&config).to_string()
+        );
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn report_file_test() -> Result<(), ConfigError> {
+        let path = std::path::PathBuf::from("target/report_test.toml");
+        std::fs::write(&path, "[server]\nhost = \"localhost\"\nport = \"abc\"\n")
+            .map_err(ConfigError::from_cause)?;
+        let config = Configuration::new().register_file(path.clone(), true)?;
+        let e = config.get::<Server>("server").unwrap_err();
+        assert_eq!(
+            "error: Configuration error caused by: invalid digit found in string
+ --> target/report_test.toml:3:1
+  |
+3 | port = \"abc\"
+  | ^ invalid value
+  = key: server.port
+  = source: file:target/report_test.toml.[toml,tml]
+  = in: Server.port
+",
+            e.report().to_string()
+        );
+
+        #[derive(Debug, FromConfig)]
+        #[config(crate = "crate")]
+        #[allow(dead_code)]
+        struct Db {
+            password: u16,
+            #[config(secret)]
+            pin: Option<u16>,
+        }
+        std::fs::write(&path, "[db]\npassword = \"hunter2\"\n").map_err(ConfigError::from_cause)?;
+        let config = Configuration::new().register_file(path.clone(), true)?;
+        let e = config.get::<Db>("db").unwrap_err();
+        assert_eq!(
+            "error: Configuration error caused by: invalid digit found in string
+ --> target/report_test.toml:2:1
+  = key: db.password
+  = source: file:target/report_test.toml.[toml,tml]
+  = in: Db.password
+",
+            e.report().to_string()
+        );
+        std::fs::write(&path, "[db]\npassword = 1\npin = \"hunter2\"\n")
+            .map_err(ConfigError::from_cause)?;
+        let config = Configuration::new().register_file(path.clone(), true)?;
+        let report = config.get::<Db>("db").unwrap_err().report().to_string();
+        assert!(!report.contains("hunter2"), "{}", report);
+
+        std::fs::write(&path, "[server]\nhost = = 1\n").map_err(ConfigError::from_cause)?;
+        let e = Configuration::new()
+            .register_file(path.clone(), true)
+            .err()
+            .unwrap();
+        let report = e.report().to_string();
+        assert!(
+            report.contains("2 | host = = 1\n  |        ^ syntax error\n"),
+            "{}",
+            report
+        );
+        std::fs::remove_file(path).map_err(ConfigError::from_cause)?;
+        Ok(())
+    }
+
+    #[cfg(any(feature = "toml", feature = "yaml", feature = "json"))]
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    #[allow(dead_code)]
+    struct Flags {
+        port: u16,
+        enabled: bool,
+    }
+
+    /// Report of parsing [`Flags`] from file `name`.
+    #[cfg(any(feature = "toml", feature = "yaml", feature = "json"))]
+    fn flags_report(
+        name: &str,
+        content: &str,
+        policy: CoercionPolicy,
+    ) -> Result<String, ConfigError> {
+        let path = std::path::PathBuf::from(format!("target/{}", name));
+        std::fs::write(&path, content).map_err(ConfigError::from_cause)?;
+        let config = Configuration::new()
+            .register_file(path.clone(), false)?
+            .set_coercion_policy(policy);
+        let report = config
+            .get::<Flags>("flags")
+            .unwrap_err()
+            .report()
+            .with_config(&config)
+            .to_string();
+        std::fs::remove_file(path).map_err(ConfigError::from_cause)?;
+        Ok(report)
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn report_toml_test() -> Result<(), ConfigError> {
+        let name = "report_flags.toml";
+        let mismatch = flags_report(
+            name,
+            "[flags]\nport = true\nenabled = true\n",
+            CoercionPolicy::Strict,
+        )?;
+        let parse = flags_report(
+            name,
+            "[flags]\nport = 80\nenabled = \"maybe\"\n",
+            CoercionPolicy::Default,
+        )?;
+        assert_eq!(
+            "error: Configuration type mismatch for key 'flags.port': expected u16, found Bool
+ --> target/report_flags.toml:2:1
+  |
+2 | port = true
+  | ^ expected u16
+  = key: flags.port
+  = source: file:target/report_flags.toml.[toml,tml]
+  = in: Flags.port
+  = help: change the value to u16, Bool is rejected by strict coercion policy
+",
+            mismatch
+        );
+        assert_eq!(
+            "error: Configuration parse error for key 'flags.enabled': maybe
+ --> target/report_flags.toml:3:1
+  |
+3 | enabled = \"maybe\"
+  | ^ invalid value
+  = key: flags.enabled
+  = source: file:target/report_flags.toml.[toml,tml]
+  = in: Flags.enabled
+",
+            parse
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn report_yaml_test() -> Result<(), ConfigError> {
+        let name = "report_flags.yaml";
+        let mismatch = flags_report(
+            name,
+            "flags:\n  port: true\n  enabled: true\n",
+            CoercionPolicy::Strict,
+        )?;
+        let parse = flags_report(
+            name,
+            "flags:\n  port: 80\n  enabled: maybe\n",
+            CoercionPolicy::Default,
+        )?;
+        assert_eq!(
+            "error: Configuration type mismatch for key 'flags.port': expected u16, found Bool
+ --> target/report_flags.yaml:2:3
+  |
+2 |   port: true
+  |   ^ expected u16
+  = key: flags.port
+  = source: file:target/report_flags.yaml.[yaml,yml]
+  = in: Flags.port
+  = help: change the value to u16, Bool is rejected by strict coercion policy
+",
+            mismatch
+        );
+        assert_eq!(
+            "error: Configuration parse error for key 'flags.enabled': maybe
+ --> target/report_flags.yaml:3:3
+  |
+3 |   enabled: maybe
+  |   ^ invalid value
+  = key: flags.enabled
+  = source: file:target/report_flags.yaml.[yaml,yml]
+  = in: Flags.enabled
+",
+            parse
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn report_json_test() -> Result<(), ConfigError> {
+        let name = "report_flags.json";
+        let mismatch = flags_report(
+            name,
+            "{\n  \"flags\": {\n    \"port\": true,\n    \"enabled\": true\n  }\n}\n",
+            CoercionPolicy::Strict,
+        )?;
+        let parse = flags_report(
+            name,
+            "{\n  \"flags\": {\n    \"port\": 80,\n    \"enabled\": \"maybe\"\n  }\n}\n",
+            CoercionPolicy::Default,
+        )?;
+        assert_eq!(
+            "error: Configuration type mismatch for key 'flags.port': expected u16, found Bool
+ --> target/report_flags.json:3:5
+  |
+3 |     \"port\": true,
+  |     ^ expected u16
+  = key: flags.port
+  = source: file:target/report_flags.json.[json]
+  = in: Flags.port
+  = help: change the value to u16, Bool is rejected by strict coercion policy
+",
+            mismatch
+        );
+        assert_eq!(
+            "error: Configuration parse error for key 'flags.enabled': maybe
+ --> target/report_flags.json:4:5
+  |
+4 |     \"enabled\": \"maybe\"
+  |     ^ invalid value
+  = key: flags.enabled
+  = source: file:target/report_flags.json.[json]
+  = in: Flags.enabled
+",
+            parse
+        );
+        Ok(())
+    }
+}
@@ -6,11 +6,11 @@ use std::path::PathBuf;
 /// Configuration Error.
 #[derive(Debug)]
 pub enum ConfigError {
-    /// Config not found.
+    /// Config not found, see [`Configuration::suggestions`] for similar keys.
     ConfigNotFound(String),
     /// Config not found when parsing placeholder.
     ConfigRecursiveNotFound(String),
-    /// Config type mismatch.
+    /// Config type mismatch, with key, found type and expected type.
     ConfigTypeMismatch(String, &'static str, &'static str),
     /// Config parse error.
     ConfigParseError(String, String),
@@ -26,19 +26,333 @@ pub enum ConfigError {
     TooManyInstances(usize),
     /// Lock failed.
     LockPoisoned,
-    /// Config parse error with other error.
-    ConfigCause(Box<dyn Error + 'static>),
+    /// Config parse error with other error, or other variant with context.
+    ///
+    /// See [`ConfigError::context`] for where it happens and [`ConfigError::kind`] for the variant it wraps.
+    ConfigCause(Box<dyn Error + Send + Sync + 'static>),
+}
+
+/// Context of error returned by [`ConfigContext`], shows where the failure happens.
+#[derive(Debug, Clone, Default)]
+pub struct ErrorContext {
+    /// Failing key.
+    pub key: Option<String>,
+    /// Name of source which supplies the failing value or fails to load.
+    pub source: Option<String>,
+    /// Config file path.
+    pub path: Option<PathBuf>,
+    /// Line number in config file, starts from 1.
+    pub line: Option<usize>,
+    /// Column number in config file, starts from 1.
+    pub column: Option<usize>,
+    /// Keys and types being built, from outermost to innermost.
+    pub frames: Vec<(String, &'static str)>,
+    /// Similar keys of missing key, see [`ConfigError::suggestions`].
+    pub suggestions: Vec<String>,
+    /// Coercion policy which rejects the value of [`ConfigError::ConfigTypeMismatch`].
+    pub policy: Option<CoercionPolicy>,
+}
+
+impl ErrorContext {
+    /// Chain of types and fields being built, such as `AppConfig.db -> PostgresConfig.pool -> PoolConfig.max_size`.
+    pub fn chain(&self) -> String {
+        self.frames
+            .windows(2)
+            .map(|w| {
+                let sub = w[1].0.strip_prefix(w[0].0.as_str()).unwrap_or(&w[1].0);
+                match sub.strip_prefix('.') {
+                    Some(sub) => format!("{}.{}", short_type(w[0].1), sub),
+                    None => format!("{}{}", short_type(w[0].1), sub),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+}
+
+/// Remove module paths from type name, `alloc::vec::Vec<app::Db>` => `Vec<Db>`.
+fn short_type(name: &str) -> String {
+    let mut out = String::new();
+    let mut word = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            word.clear();
+        } else if c.is_alphanumeric() || c == '_' {
+            word.push(c);
+        } else {
+            out.push_str(&word);
+            word.clear();
+            out.push(c);
+        }
+    }
+    out.push_str(&word);
+    out
+}
+
+impl ErrorContext {
+    /// Describe context, key is skipped if `with_key` is false.
+    fn describe(&self, with_key: bool) -> String {
+        let mut parts = vec![];
+        if let Some(policy) = self.policy {
+            parts.push(format!("rejected by {} coercion policy", policy));
+        }
+        if let Some(key) = self.key.as_ref().filter(|_| with_key) {
+            parts.push(format!("key '{}'", key));
+        }
+        if let Some(source) = &self.source {
+            parts.push(format!("source '{}'", source));
+        }
+        if let Some(path) = &self.path {
+            let mut at = format!("at {}", path.display());
+            if let Some(line) = self.line {
+                at.push_str(&format!(":{}", line));
+                if let Some(column) = self.column {
+                    at.push_str(&format!(":{}", column));
+                }
+            }
+            parts.push(at);
+        } else if let (Some(line), Some(column)) = (self.line, self.column) {
+            parts.push(format!("at line {} column {}", line, column));
+        }
+        if self.frames.len() > 1 {
+            parts.push(format!("in {}", self.chain()));
+        }
+        parts.join(", ")
+    }
+}
+
+impl Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.describe(true))
+    }
+}
+
+/// Keys never read, returned by [`Configuration::check_unused`] as cause of [`ConfigError::ConfigCause`].
+///
+/// ```rust
+/// use cfg_rs::*;
+/// use std::error::Error;
+/// let config = Configuration::new()
+///     .register_kv("kv")
+///     .set("app.prot", 8080)
+///     .finish()
+///     .unwrap();
+/// let err = config.check_unused("app").unwrap_err();
+/// let keys = err.source().and_then(|e| e.downcast_ref::<UnusedKeys>()).unwrap();
+/// assert_eq!(vec!["app.prot"], keys.0);
+/// ```
+#[derive(Debug)]
+pub struct UnusedKeys(pub Vec<String>);
+
+impl Display for UnusedKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "keys not used: {}", self.0.join(", "))
+    }
+}
+
+impl Error for UnusedKeys {}
+
+/// Cause with context, kept in [`ConfigError::ConfigCause`] so that the variant is still matchable.
+struct ContextCause {
+    cause: Box<dyn Error + Send + Sync + 'static>,
+    context: ErrorContext,
+}
+
+impl std::fmt::Debug for ContextCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.cause, f)
+    }
+}
+
+impl Display for ContextCause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.cause)?;
+        if !self.context.suggestions.is_empty() {
+            write!(
+                f,
+                ", did you mean {}?",
+                self.context.suggestions.join(" or ")
+            )?;
+        }
+        // Other variants already show the key.
+        let context = self.context.describe(!self.cause.is::<ConfigError>());
+        if !context.is_empty() {
+            write!(f, " ({})", context)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ContextCause {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.cause.downcast_ref::<ConfigError>() {
+            Some(e) => e.source(),
+            None => Some(self.cause.as_ref()),
+        }
+    }
 }
 
 impl ConfigError {
     #[inline]
     /// Creates a `ConfigError` from another error type.
-    pub fn from_cause<E: Error + 'static>(e: E) -> Self {
+    pub fn from_cause<E: Error + Send + Sync + 'static>(e: E) -> Self {
         ConfigError::ConfigCause(Box::new(e))
     }
+
+    /// Creates a `ConfigError` from parse error at `line` and `column` of config content.
+    pub fn from_cause_at<E: Error + Send + Sync + 'static>(
+        e: E,
+        line: usize,
+        column: usize,
+    ) -> Self {
+        Self::from_cause(e).with_context(|c| {
+            c.line = Some(line);
+            c.column = Some(column);
+        })
+    }
+
+    /// Context of error, such as key, source and file location.
+    ///
+    /// Errors other than [`ConfigError::ConfigCause`] are wrapped into it once context is added,
+    /// use [`ConfigError::kind`] to match the original variant.
+    /// The original cause is returned by [`Error::source`].
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// use std::error::Error;
+    /// let config = Configuration::new()
+    ///     .register_kv("kv")
+    ///     .set("port", "abc")
+    ///     .set("enabled", "maybe")
+    ///     .finish()
+    ///     .unwrap();
+    /// let err = config.get::<u16>("port").unwrap_err();
+    /// assert!(matches!(err, ConfigError::ConfigCause(_)));
+    /// assert_eq!(Some("port"), err.context().and_then(|c| c.key.as_deref()));
+    /// assert!(err.source().unwrap().is::<std::num::ParseIntError>());
+    /// let err = config.get::<bool>("enabled").unwrap_err();
+    /// assert!(matches!(err.kind(), ConfigError::ConfigParseError(k, _) if k == "enabled"));
+    /// assert_eq!(Some("kv"), err.context().and_then(|c| c.source.as_deref()));
+    /// ```
+    pub fn context(&self) -> Option<&ErrorContext> {
+        self.context_cause().map(|c| &c.context)
+    }
+
+    /// Similar keys of missing key in [`ConfigError::ConfigNotFound`] returned by [`Configuration::get`],
+    /// see [`Configuration::suggestions`].
+    ///
+    /// ```rust
+    /// use cfg_rs::*;
+    /// let config = Configuration::new()
+    ///     .register_kv("kv")
+    ///     .set("app.port", 8080)
+    ///     .finish()
+    ///     .unwrap();
+    /// let err = config.get::<u16>("app.prot").unwrap_err();
+    /// assert!(matches!(err.kind(), ConfigError::ConfigNotFound(_)));
+    /// assert_eq!(["app.port"], err.suggestions());
+    /// assert_eq!(
+    ///     "Configuration not found: app.prot, did you mean app.port?",
+    ///     err.to_string()
+    /// );
+    /// ```
+    pub fn suggestions(&self) -> &[String] {
+        self.context().map_or(&[], |c| &c.suggestions)
+    }
+
+    /// Variant of error without context.
+    ///
+    /// Returns the wrapped error if context is added to other variant than [`ConfigError::ConfigCause`],
+    /// or the error itself.
+    pub fn kind(&self) -> &ConfigError {
+        match self
+            .context_cause()
+            .and_then(|c| c.cause.downcast_ref::<ConfigError>())
+        {
+            Some(e) => e,
+            None => self,
+        }
+    }
+
+    fn context_cause(&self) -> Option<&ContextCause> {
+        match self {
+            ConfigError::ConfigCause(e) => e.downcast_ref::<ContextCause>(),
+            _ => None,
+        }
+    }
+
+    /// Update context of error, add context if not exists.
+    pub(crate) fn with_context<F: FnOnce(&mut ErrorContext)>(self, f: F) -> Self {
+        let cause: Box<dyn Error + Send + Sync + 'static> = match self {
+            ConfigError::ConfigCause(mut e) => {
+                if let Some(c) = e.downcast_mut::<ContextCause>() {
+                    f(&mut c.context);
+                    return ConfigError::ConfigCause(e);
+                }
+                e
+            }
+            e => Box::new(e),
+        };
+        let mut context = ErrorContext::default();
+        f(&mut context);
+        ConfigError::ConfigCause(Box::new(ContextCause { cause, context }))
+    }
+
+    /// Replace variant of error, keep context if exists.
+    pub(crate) fn map_kind<F: FnOnce(ConfigError) -> ConfigError>(self, f: F) -> Self {
+        match self {
+            ConfigError::ConfigCause(e) => match e.downcast::<ContextCause>() {
+                Ok(c) => {
+                    let ContextCause { cause, context } = *c;
+                    let kind = match cause.downcast::<ConfigError>() {
+                        Ok(k) => f(*k),
+                        Err(cause) => f(ConfigError::ConfigCause(cause)),
+                    };
+                    kind.with_context(|c| *c = context)
+                }
+                Err(e) => f(ConfigError::ConfigCause(e)),
+            },
+            e => f(e),
+        }
+    }
+
+    /// Display of error without context.
+    #[cfg(feature = "diagnostics")]
+    pub(crate) fn message(&self) -> String {
+        match self {
+            ConfigError::ConfigCause(e) => match e.downcast_ref::<ContextCause>() {
+                Some(c) => match c.cause.downcast_ref::<ConfigError>() {
+                    Some(k) => k.to_string(),
+                    None => format!("Configuration error caused by: {}", c.cause),
+                },
+                None => self.to_string(),
+            },
+            e => e.to_string(),
+        }
+    }
+
+    /// Add source name and file path to error of loading file.
+    pub(crate) fn with_file(self, source: &str, path: &std::path::Path) -> Self {
+        self.with_context(|c| {
+            c.source.get_or_insert_with(|| source.to_string());
+            c.path.get_or_insert_with(|| path.to_path_buf());
+        })
+    }
 }
 
-impl Error for ConfigError {}
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::ConfigCause(e) => match e.downcast_ref::<ContextCause>() {
+                Some(c) => c.source(),
+                None => Some(e.as_ref()),
+            },
+            _ => None,
+        }
+    }
+}
 
 impl Display for ConfigError {
     #[inline]
@@ -50,7 +364,7 @@ impl Display for ConfigError {
             ConfigError::ConfigRecursiveNotFound(key) => {
                 write!(f, "Configuration recursive not found: {}", key)
             }
-            ConfigError::ConfigTypeMismatch(key, expected, found) => {
+            ConfigError::ConfigTypeMismatch(key, found, expected) => {
                 write!(
                     f,
                     "Configuration type mismatch for key '{}': expected {}, found {}",
@@ -78,11 +392,55 @@ impl Display for ConfigError {
             ConfigError::LockPoisoned => {
                 write!(f, "Lock poisoned")
             }
-            ConfigError::ConfigCause(e) => {
-                write!(f, "Configuration error caused by: {}", e)
+            ConfigError::ConfigCause(e) => match e.downcast_ref::<ContextCause>() {
+                Some(c) if c.cause.is::<ConfigError>() => write!(f, "{}", c),
+                _ => write!(f, "Configuration error caused by: {}", e),
+            },
+        }
+    }
+}
+
+/// Optimal string alignment distance, adjacent transpositions count as one edit.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, v) in d[0].iter_mut().enumerate() {
+        *v = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut v = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                v = v.min(d[i - 2][j - 2] + 1);
             }
+            d[i][j] = v;
         }
     }
+    d[a.len()][b.len()]
+}
+
+/// Candidates close to `target`, nearest first.
+pub(crate) fn similar<'a, I: IntoIterator<Item = &'a str>>(
+    target: &str,
+    candidates: I,
+) -> Vec<&'a str> {
+    let len = target.chars().count();
+    let max = (len / 3).max(1).min(len.saturating_sub(1));
+    let mut v: Vec<_> = candidates
+        .into_iter()
+        .filter(|c| *c != target)
+        .map(|c| (edit_distance(&target.to_lowercase(), &c.to_lowercase()), c))
+        .filter(|(d, _)| *d <= max)
+        .collect();
+    v.sort();
+    v.into_iter().map(|(_, c)| c).collect()
 }
 
 impl ConfigError {
@@ -148,7 +506,7 @@ mod tests {
 
     #[test]
     fn display_config_type_mismatch() {
-        let e = ConfigError::ConfigTypeMismatch("app.port".into(), "u16", "String");
+        let e = ConfigError::ConfigTypeMismatch("app.port".into(), "String", "u16");
         assert_eq!(
             format!("{}", e),
             "Configuration type mismatch for key 'app.port': expected u16, found String"
@@ -208,6 +566,15 @@ mod tests {
         assert_eq!(format!("{}", e), "Lock poisoned");
     }
 
+    #[test]
+    fn display_config_unused_keys() {
+        let e = ConfigError::from_cause(UnusedKeys(vec!["app.prot".into(), "app.hots".into()]));
+        assert_eq!(
+            format!("{}", e),
+            "Configuration error caused by: keys not used: app.prot, app.hots"
+        );
+    }
+
     #[test]
     fn display_config_cause() {
         let io_err = std::io::Error::other("io");
@@ -215,6 +582,37 @@ mod tests {
         assert_eq!(format!("{}", e), "Configuration error caused by: io");
     }
 
+    #[test]
+    fn send_sync_test() {
+        fn assert_send_sync<T: Send + Sync + 'static>() {}
+        assert_send_sync::<ConfigError>();
+        let e = thread::spawn(|| ConfigError::from_cause(std::io::Error::other("io")))
+            .join()
+            .unwrap();
+        let e: Box<dyn Error + Send + Sync> = Box::new(e);
+        assert_eq!("io", e.source().unwrap().to_string());
+    }
+
+    #[test]
+    fn source_test() {
+        assert!(ConfigError::ConfigNotFound("a".into()).source().is_none());
+        let e = ConfigError::from_cause_at(std::io::Error::other("io"), 1, 2);
+        assert_eq!("io", e.source().unwrap().to_string());
+        let mut chain = vec![];
+        let mut curr: Option<&(dyn Error + 'static)> = Some(&e);
+        while let Some(v) = curr {
+            chain.push(v.to_string());
+            curr = v.source();
+        }
+        assert_eq!(
+            vec![
+                "Configuration error caused by: io (at line 1 column 2)".to_string(),
+                "io".to_string()
+            ],
+            chain
+        );
+    }
+
     #[test]
     fn config_error_from_converts_to_configcause() {
         let io_err = std::io::Error::other("io");
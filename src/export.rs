@@ -0,0 +1,294 @@
+//! Export configuration as text.
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+};
+
+use crate::{ConfigError, ConfigTree, ConfigValue};
+
+/// Export format, see [`Configuration::export`](struct.Configuration.html#method.export).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Toml document.
+    Toml,
+    /// Yaml document.
+    Yaml,
+    /// Json document.
+    Json,
+    /// Properties, one `key=value` per line, such as `app.hosts[0]=a`.
+    Properties,
+    /// Env variables, one `KEY=value` per line, such as `APP_HOSTS_0=a`, prefix is not included.
+    ///
+    /// Names are upper cased and `.`, `[` and `-` become `_`, so names may not be read back as the same keys,
+    /// keys exported as the same name, such as `app.db-url` and `app.db_url`, return an error.
+    Env,
+}
+
+const REDACTED: &str = "***";
+
+/// Match key by glob pattern, `*` matches any characters, case insensitive.
+pub(crate) fn glob_match(pattern: &str, key: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let key = key.to_lowercase();
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (i, p) in parts.iter().enumerate() {
+        if i == parts.len() - 1 {
+            return rest.ends_with(p);
+        }
+        match rest.find(p) {
+            Some(pos) => rest = &rest[pos + p.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// Replace values of keys matching `patterns` by `***`.
+pub(crate) fn redact(tree: ConfigTree, key: &str, patterns: &[String]) -> ConfigTree {
+    if !key.is_empty() && patterns.iter().any(|p| glob_match(p, key)) {
+        return ConfigTree::Value(ConfigValue::StrRef(REDACTED));
+    }
+    match tree {
+        ConfigTree::Map(v) => ConfigTree::Map(
+            v.into_iter()
+                .map(|(k, t)| {
+                    let key = join_key(key, &k);
+                    (k, redact(t, &key, patterns))
+                })
+                .collect(),
+        ),
+        ConfigTree::Array(v) => ConfigTree::Array(
+            v.into_iter()
+                .enumerate()
+                .map(|(i, t)| redact(t, &format!("{}[{}]", key, i), patterns))
+                .collect(),
+        ),
+        v => v,
+    }
+}
+
+fn join_key(key: &str, sub: &str) -> String {
+    if key.is_empty() {
+        sub.to_string()
+    } else {
+        format!("{}.{}", key, sub)
+    }
+}
+
+/// Quote string like json, which is also valid in toml and yaml.
+fn quote(s: &str) -> String {
+    ConfigTree::Value(ConfigValue::Str(s.to_string())).to_string()
+}
+
+/// Scalar text, strings are quoted if `quoted`.
+fn scalar(v: &ConfigValue<'_>, quoted: bool) -> String {
+    match v {
+        ConfigValue::StrRef(s) if quoted => quote(s),
+        ConfigValue::Str(s) if quoted => quote(s),
+        ConfigValue::StrRef(s) => s.to_string(),
+        ConfigValue::Str(s) => s.to_string(),
+        ConfigValue::Null if quoted => "null".to_string(),
+        ConfigValue::Null => String::new(),
+        ConfigValue::Float(v) if !v.is_finite() => v.to_string(),
+        v => ConfigTree::Value(v.clone_static()).to_string(),
+    }
+}
+
+pub(crate) fn export(tree: &ConfigTree, format: Format) -> Result<String, ConfigError> {
+    let mut buf = String::new();
+    match format {
+        Format::Json => buf = tree.to_string(),
+        Format::Properties => flatten(tree, "", &mut |k, v| {
+            let _ = writeln!(buf, "{}={}", k, escape_line(&scalar(v, false)));
+        }),
+        Format::Env => {
+            let mut names: HashMap<String, String> = HashMap::new();
+            let mut err = None;
+            flatten(tree, "", &mut |k, v| {
+                let name: String = k
+                    .chars()
+                    .filter(|c| *c != ']')
+                    .map(|c| match c {
+                        '.' | '[' | '-' => '_',
+                        c => c.to_ascii_uppercase(),
+                    })
+                    .collect();
+                if let Some(other) = names.insert(name.clone(), k.to_string()) {
+                    err.get_or_insert_with(|| {
+                        ConfigError::ConfigParseError(
+                            k.to_string(),
+                            format!("env name {} is also exported by key {}", name, other),
+                        )
+                    });
+                }
+                let _ = writeln!(buf, "{}={}", name, quote(&scalar(v, false)));
+            });
+            if let Some(e) = err {
+                return Err(e);
+            }
+        }
+        Format::Yaml => write_yaml(tree, 0, &mut buf),
+        Format::Toml => {
+            if let ConfigTree::Map(v) = tree {
+                write_toml_table(v, "", &mut buf);
+            }
+        }
+    }
+    Ok(buf)
+}
+
+fn escape_line(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn flatten<F: FnMut(&str, &ConfigValue<'_>)>(tree: &ConfigTree, key: &str, f: &mut F) {
+    match tree {
+        ConfigTree::Value(v) => f(key, v),
+        ConfigTree::Array(v) => {
+            for (i, t) in v.iter().enumerate() {
+                flatten(t, &format!("{}[{}]", key, i), f);
+            }
+        }
+        ConfigTree::Map(v) => {
+            for (k, t) in v {
+                flatten(t, &join_key(key, k), f);
+            }
+        }
+    }
+}
+
+fn write_yaml(tree: &ConfigTree, indent: usize, buf: &mut String) {
+    let pad = " ".repeat(indent);
+    match tree {
+        ConfigTree::Value(v) => {
+            let _ = writeln!(buf, "{}", scalar(v, true));
+        }
+        ConfigTree::Array(v) if v.is_empty() => buf.push_str("[]\n"),
+        ConfigTree::Map(v) if v.is_empty() => buf.push_str("{}\n"),
+        ConfigTree::Array(v) => {
+            for (i, t) in v.iter().enumerate() {
+                if i > 0 || indent > 0 {
+                    buf.push_str(&pad);
+                }
+                buf.push_str("- ");
+                write_yaml_node(t, indent + 2, buf);
+            }
+        }
+        ConfigTree::Map(v) => {
+            for (i, (k, t)) in v.iter().enumerate() {
+                if i > 0 || indent > 0 {
+                    buf.push_str(&pad);
+                }
+                let _ = write!(buf, "{}:", quote(k));
+                match t {
+                    ConfigTree::Value(_) => buf.push(' '),
+                    ConfigTree::Array(v) if v.is_empty() => buf.push(' '),
+                    ConfigTree::Map(v) if v.is_empty() => buf.push(' '),
+                    _ => buf.push('\n'),
+                }
+                write_yaml(t, indent + 2, buf);
+            }
+        }
+    }
+}
+
+/// Write node after `- `, nested maps and arrays start at the same line.
+fn write_yaml_node(tree: &ConfigTree, indent: usize, buf: &mut String) {
+    match tree {
+        ConfigTree::Array(v) if !v.is_empty() => {
+            buf.push_str("- ");
+            for (i, t) in v.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(&" ".repeat(indent));
+                    buf.push_str("- ");
+                }
+                write_yaml_node(t, indent + 2, buf);
+            }
+        }
+        ConfigTree::Map(v) if !v.is_empty() => {
+            let mut first = String::new();
+            write_yaml(tree, indent, &mut first);
+            buf.push_str(first.trim_start());
+        }
+        t => write_yaml(t, indent, buf),
+    }
+}
+
+fn toml_key(k: &str) -> String {
+    if !k.is_empty()
+        && k.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    {
+        k.to_string()
+    } else {
+        quote(k)
+    }
+}
+
+fn is_table_array(v: &[ConfigTree]) -> bool {
+    !v.is_empty() && v.iter().all(|t| matches!(t, ConfigTree::Map(_)))
+}
+
+fn toml_inline(tree: &ConfigTree) -> String {
+    match tree {
+        // Toml spells special floats in lower case, unlike `f64` display.
+        ConfigTree::Value(ConfigValue::Float(v)) if v.is_nan() => "nan".to_string(),
+        ConfigTree::Value(ConfigValue::Float(v)) if v.is_infinite() && *v > 0.0 => {
+            "inf".to_string()
+        }
+        ConfigTree::Value(ConfigValue::Float(v)) if v.is_infinite() => "-inf".to_string(),
+        ConfigTree::Value(v) => scalar(v, true),
+        ConfigTree::Array(v) => format!(
+            "[{}]",
+            v.iter().map(toml_inline).collect::<Vec<_>>().join(", ")
+        ),
+        ConfigTree::Map(v) => format!(
+            "{{{}}}",
+            v.iter()
+                .map(|(k, t)| format!("{} = {}", toml_key(k), toml_inline(t)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn write_toml_table(map: &BTreeMap<String, ConfigTree>, key: &str, buf: &mut String) {
+    for (k, t) in map {
+        match t {
+            ConfigTree::Map(_) => {}
+            ConfigTree::Array(v) if is_table_array(v) => {}
+            t => {
+                let _ = writeln!(buf, "{} = {}", toml_key(k), toml_inline(t));
+            }
+        }
+    }
+    for (k, t) in map {
+        let sub = if key.is_empty() {
+            toml_key(k)
+        } else {
+            format!("{}.{}", key, toml_key(k))
+        };
+        match t {
+            ConfigTree::Map(v) => {
+                let _ = writeln!(buf, "\n[{}]", sub);
+                write_toml_table(v, &sub, buf);
+            }
+            ConfigTree::Array(v) if is_table_array(v) => {
+                for t in v {
+                    let _ = writeln!(buf, "\n[[{}]]", sub);
+                    if let ConfigTree::Map(v) = t {
+                        write_toml_table(v, &sub, buf);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,697 @@
+//! Expressions in placeholders, see [`Configuration::set_expressions`](struct.Configuration.html#method.set_expressions).
+use std::fmt::{Display, Formatter};
+
+use crate::{ConfigError, value::float_to_int};
+
+/// Max nesting depth of expressions.
+const MAX_DEPTH: usize = 64;
+
+/// Operators by precedence, from lowest to highest.
+const BINARY_OPS: [&[&str]; 5] = [
+    &["||"],
+    &["&&"],
+    &["==", "!=", "<=", ">=", "<", ">"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+/// Two-char operators go first.
+const OPS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!", "?", ":", "(", ")",
+    ",",
+];
+
+/// Value of expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    /// Number read from config, with its original text, such as `007` and `1.10`.
+    Num(String, Box<Value>),
+}
+
+impl Value {
+    /// Infer value from config text, integers, floats and bools are typed, others are strings.
+    ///
+    /// Numbers keep their text, which is used when they are joined with strings.
+    pub(crate) fn infer(v: String) -> Self {
+        if let Ok(i) = v.parse() {
+            return Value::Num(v, Box::new(Value::Int(i)));
+        }
+        match v.as_str() {
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => {}
+        }
+        // Skip `inf`, `NaN` and so on.
+        if v.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.')
+            && v.bytes()
+                .all(|b| b.is_ascii_digit() || b"+-.eE".contains(&b))
+        {
+            if let Ok(f) = v.parse() {
+                return Value::Num(v, Box::new(Value::Float(f)));
+            }
+        }
+        Value::Str(v)
+    }
+
+    /// Drop original text of number.
+    fn number(self) -> Self {
+        match self {
+            Value::Num(_, v) => *v,
+            v => v,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "string",
+            Value::Num(_, v) => v.type_name(),
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(v) => Some(*v as f64),
+            Value::Float(v) => Some(*v),
+            Value::Num(_, v) => v.as_f64(),
+            _ => None,
+        }
+    }
+
+    fn as_bool(&self) -> Result<bool, String> {
+        match self {
+            Value::Bool(v) => Ok(*v),
+            v => Err(format!("expected bool, found {}", v.type_name())),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Str(v) => write!(f, "{}", v),
+            Value::Num(v, _) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Value(Value),
+    Ident(String),
+    Op(&'static str),
+}
+
+#[derive(Debug)]
+enum Expr {
+    Value(Value),
+    Key(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Check if placeholder is an expression rather than `key`, `key:default` or `scheme:value`,
+/// i.e. text before the first `:` has chars other than key chars.
+pub(crate) fn is_expression(v: &str) -> bool {
+    let head = v.split(':').next().unwrap_or(v);
+    head.chars()
+        .any(|c| !(c.is_alphanumeric() || "_.[]-@".contains(c)))
+}
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || c == '[' || c == ']'
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut rest = expr.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() {
+            let int = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let frac = rest[int..]
+                .strip_prefix('.')
+                .filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+                .map_or(0, |v| {
+                    1 + v.find(|c: char| !c.is_ascii_digit()).unwrap_or(v.len())
+                });
+            let num = &rest[..int + frac];
+            tokens.push(Token::Value(if frac == 0 {
+                Value::Int(num.parse().map_err(|_| format!("invalid number {}", num))?)
+            } else {
+                Value::Float(num.parse().map_err(|_| format!("invalid number {}", num))?)
+            }));
+            num.len()
+        } else if c == '\'' || c == '"' {
+            let mut v = String::new();
+            let mut chars = rest.char_indices().skip(1);
+            let mut end = None;
+            while let Some((i, ch)) = chars.next() {
+                match ch {
+                    '\\' => match chars.next() {
+                        Some((_, ch)) => v.push(ch),
+                        None => break,
+                    },
+                    ch if ch == c => {
+                        end = Some(i + 1);
+                        break;
+                    }
+                    ch => v.push(ch),
+                }
+            }
+            tokens.push(Token::Value(Value::Str(v)));
+            end.ok_or_else(|| "unterminated string".to_string())?
+        } else if c.is_alphabetic() || c == '_' {
+            // `-` is part of kebab-case keys when followed by a letter, such as `max-size`.
+            let mut end = rest.len();
+            let mut chars = rest.char_indices().peekable();
+            while let Some((i, ch)) = chars.next() {
+                let next = chars.peek().map(|(_, c)| *c);
+                if !(is_key_char(ch)
+                    || (ch == '-' && next.is_some_and(|c| c.is_alphabetic() || c == '_')))
+                {
+                    end = i;
+                    break;
+                }
+            }
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            end
+        } else {
+            let op = OPS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("unexpected char `{}`", c))?;
+            tokens.push(Token::Op(op));
+            op.len()
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.tokens.get(self.pos), Some(Token::Op(v)) if *v == op) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.eat(op) {
+            return Ok(());
+        }
+        Err(match self.tokens.get(self.pos) {
+            Some(t) => format!("expected `{}`, found {}", op, describe(t)),
+            None => format!("expected `{}`, found end", op),
+        })
+    }
+
+    fn enter(&mut self) -> Result<(), String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is too deep".to_string());
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.enter()?;
+        let cond = self.binary(0)?;
+        let v = if self.eat("?") {
+            let a = self.expr()?;
+            self.expect(":")?;
+            let b = self.expr()?;
+            Expr::Cond(Box::new(cond), Box::new(a), Box::new(b))
+        } else {
+            cond
+        };
+        self.depth -= 1;
+        Ok(v)
+    }
+
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        if level == BINARY_OPS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.binary(level + 1)?;
+        while let Some(op) = BINARY_OPS[level].iter().find(|op| self.eat(op)) {
+            let rhs = self.binary(level + 1)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        for op in ["!", "-"] {
+            if self.eat(op) {
+                self.enter()?;
+                let v = self.unary()?;
+                self.depth -= 1;
+                return Ok(Expr::Unary(op, Box::new(v)));
+            }
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        match token {
+            Some(Token::Value(v)) => Ok(Expr::Value(v)),
+            Some(Token::Ident(name)) if self.eat("(") => {
+                let mut args = vec![];
+                if !self.eat(")") {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(")") {
+                            break;
+                        }
+                        self.expect(",")?;
+                    }
+                }
+                Ok(Expr::Call(name, args))
+            }
+            Some(Token::Ident(name)) => Ok(match name.as_str() {
+                "true" => Expr::Value(Value::Bool(true)),
+                "false" => Expr::Value(Value::Bool(false)),
+                _ => Expr::Key(name),
+            }),
+            Some(Token::Op("(")) => {
+                let v = self.expr()?;
+                self.expect(")")?;
+                Ok(v)
+            }
+            Some(t) => Err(format!("unexpected {}", describe(&t))),
+            None => Err("unexpected end".to_string()),
+        }
+    }
+}
+
+fn describe(t: &Token) -> String {
+    match t {
+        Token::Value(Value::Str(v)) => format!("'{}'", v),
+        Token::Value(v) => v.to_string(),
+        Token::Ident(v) => format!("`{}`", v),
+        Token::Op(v) => format!("`{}`", v),
+    }
+}
+
+enum Error {
+    Config(ConfigError),
+    Eval(String),
+}
+
+impl From<String> for Error {
+    fn from(e: String) -> Self {
+        Error::Eval(e)
+    }
+}
+
+type Lookup<'a> = dyn FnMut(&str) -> Result<Value, ConfigError> + 'a;
+
+/// Evaluate expression of placeholder at `key`, config keys in expression are read by `lookup`.
+pub(crate) fn eval(key: &str, expr: &str, lookup: &mut Lookup<'_>) -> Result<Value, ConfigError> {
+    let err = |msg: String| {
+        ConfigError::ConfigParseError(key.to_string(), format!("{} in `{}`", msg, expr))
+    };
+    let mut parser = Parser {
+        tokens: tokenize(expr).map_err(err)?,
+        pos: 0,
+        depth: 0,
+    };
+    let e = parser.expr().map_err(err)?;
+    if let Some(t) = parser.tokens.get(parser.pos) {
+        return Err(err(format!("unexpected {}", describe(t))));
+    }
+    evaluate(&e, lookup).map_err(|e| match e {
+        Error::Config(e) => e,
+        Error::Eval(msg) => err(msg),
+    })
+}
+
+fn evaluate(e: &Expr, lookup: &mut Lookup<'_>) -> Result<Value, Error> {
+    Ok(match e {
+        Expr::Value(v) => v.clone(),
+        Expr::Key(key) => lookup(key).map_err(Error::Config)?,
+        Expr::Unary(op, v) => match (*op, evaluate(v, lookup)?.number()) {
+            ("!", v) => Value::Bool(!v.as_bool()?),
+            (_, Value::Int(v)) => {
+                Value::Int(v.checked_neg().ok_or("integer overflow".to_string())?)
+            }
+            (_, Value::Float(v)) => Value::Float(-v),
+            (op, v) => return Err(format!("cannot apply `{}` to {}", op, v.type_name()).into()),
+        },
+        Expr::Binary("&&", a, b) => {
+            Value::Bool(evaluate(a, lookup)?.as_bool()? && evaluate(b, lookup)?.as_bool()?)
+        }
+        Expr::Binary("||", a, b) => {
+            Value::Bool(evaluate(a, lookup)?.as_bool()? || evaluate(b, lookup)?.as_bool()?)
+        }
+        Expr::Binary(op, a, b) => binary(op, evaluate(a, lookup)?, evaluate(b, lookup)?)?,
+        Expr::Cond(c, a, b) => {
+            if evaluate(c, lookup)?.as_bool()? {
+                evaluate(a, lookup)?
+            } else {
+                evaluate(b, lookup)?
+            }
+        }
+        Expr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|v| evaluate(v, lookup))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(name, args)?
+        }
+    })
+}
+
+fn binary(op: &str, a: Value, b: Value) -> Result<Value, String> {
+    let mismatch = |a: &Value, b: &Value| {
+        format!(
+            "cannot apply `{}` to {} and {}",
+            op,
+            a.type_name(),
+            b.type_name()
+        )
+    };
+    if op == "+" && (matches!(a, Value::Str(_)) || matches!(b, Value::Str(_))) {
+        return Ok(Value::Str(format!("{}{}", a, b)));
+    }
+    Ok(match (op, a.number(), b.number()) {
+        ("+" | "-" | "*" | "/" | "%", Value::Int(a), Value::Int(b)) => {
+            if (op == "/" || op == "%") && b == 0 {
+                return Err("division by zero".to_string());
+            }
+            Value::Int(
+                match op {
+                    "+" => a.checked_add(b),
+                    "-" => a.checked_sub(b),
+                    "*" => a.checked_mul(b),
+                    "/" => a.checked_div(b),
+                    _ => a.checked_rem(b),
+                }
+                .ok_or("integer overflow")?,
+            )
+        }
+        ("+" | "-" | "*" | "/" | "%", a, b) => match (a.as_f64(), b.as_f64()) {
+            (Some(_), Some(y)) if (op == "/" || op == "%") && y == 0.0 => {
+                return Err("division by zero".to_string());
+            }
+            (Some(x), Some(y)) => Value::Float(match op {
+                "+" => x + y,
+                "-" => x - y,
+                "*" => x * y,
+                "/" => x / y,
+                _ => x % y,
+            }),
+            _ => return Err(mismatch(&a, &b)),
+        },
+        ("==", a, b) => Value::Bool(equals(&a, &b)),
+        ("!=", a, b) => Value::Bool(!equals(&a, &b)),
+        (_, a, b) => {
+            let ord = match (&a, &b) {
+                (Value::Str(x), Value::Str(y)) => Some(x.cmp(y)),
+                _ => match (a.as_f64(), b.as_f64()) {
+                    (Some(x), Some(y)) => x.partial_cmp(&y),
+                    _ => None,
+                },
+            }
+            .ok_or_else(|| mismatch(&a, &b))?;
+            Value::Bool(match op {
+                "<" => ord.is_lt(),
+                "<=" => ord.is_le(),
+                ">" => ord.is_gt(),
+                _ => ord.is_ge(),
+            })
+        }
+    })
+}
+
+fn equals(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(x), Some(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+fn call(name: &str, args: Vec<Value>) -> Result<Value, String> {
+    // String functions use original text of numbers, others use numbers.
+    let nums: Vec<Value> = args.iter().cloned().map(Value::number).collect();
+    let str_fn = |f: fn(&str) -> Value| match args.as_slice() {
+        [v] => Ok(f(&v.to_string())),
+        _ => Err(format!("function `{}` takes 1 argument", name)),
+    };
+    let pair = || match args.as_slice() {
+        [a, b] => Ok((a.to_string(), b.to_string())),
+        _ => Err(format!("function `{}` takes 2 arguments", name)),
+    };
+    Ok(match name {
+        "upper" => str_fn(|v| Value::Str(v.to_uppercase()))?,
+        "lower" => str_fn(|v| Value::Str(v.to_lowercase()))?,
+        "trim" => str_fn(|v| Value::Str(v.trim().to_string()))?,
+        "len" => str_fn(|v| Value::Int(v.chars().count() as i64))?,
+        "str" => str_fn(|v| Value::Str(v.to_string()))?,
+        "contains" => pair().map(|(a, b)| Value::Bool(a.contains(&b)))?,
+        "starts_with" => pair().map(|(a, b)| Value::Bool(a.starts_with(&b)))?,
+        "ends_with" => pair().map(|(a, b)| Value::Bool(a.ends_with(&b)))?,
+        "concat" => Value::Str(args.iter().map(|v| v.to_string()).collect()),
+        "replace" => match args.as_slice() {
+            [v, from, to] => Value::Str(v.to_string().replace(&from.to_string(), &to.to_string())),
+            _ => return Err("function `replace` takes 3 arguments".to_string()),
+        },
+        "substr" => match nums.as_slice() {
+            [_, Value::Int(start)] if *start >= 0 => {
+                Value::Str(args[0].to_string().chars().skip(*start as usize).collect())
+            }
+            [_, Value::Int(start), Value::Int(len)] if *start >= 0 && *len >= 0 => Value::Str(
+                args[0]
+                    .to_string()
+                    .chars()
+                    .skip(*start as usize)
+                    .take(*len as usize)
+                    .collect(),
+            ),
+            _ => {
+                return Err(
+                    "function `substr` takes a string and non-negative start and length"
+                        .to_string(),
+                );
+            }
+        },
+        "int" => match nums.as_slice() {
+            [Value::Int(v)] => Value::Int(*v),
+            [Value::Float(v)] => Value::Int(
+                float_to_int(*v)
+                    .ok_or_else(|| format!("cannot convert {} to int without loss", v))?,
+            ),
+            [Value::Str(v)] => Value::Int(
+                v.trim()
+                    .parse()
+                    .map_err(|_| format!("cannot convert '{}' to int", v))?,
+            ),
+            [v] => return Err(format!("cannot convert {} to int", v.type_name())),
+            _ => return Err("function `int` takes 1 argument".to_string()),
+        },
+        "float" => match nums.as_slice() {
+            [Value::Str(v)] => Value::Float(
+                v.trim()
+                    .parse()
+                    .map_err(|_| format!("cannot convert '{}' to float", v))?,
+            ),
+            [v] => Value::Float(
+                v.as_f64()
+                    .ok_or_else(|| format!("cannot convert {} to float", v.type_name()))?,
+            ),
+            _ => return Err("function `float` takes 1 argument".to_string()),
+        },
+        "abs" => match nums.as_slice() {
+            [Value::Int(v)] => Value::Int(v.checked_abs().ok_or("integer overflow")?),
+            [Value::Float(v)] => Value::Float(v.abs()),
+            _ => return Err("function `abs` takes 1 number".to_string()),
+        },
+        "min" | "max" => match args.as_slice() {
+            [a, b] => {
+                let lt = binary("<", a.clone(), b.clone())?.as_bool()?;
+                if lt == (name == "min") {
+                    a.clone()
+                } else {
+                    b.clone()
+                }
+            }
+            _ => return Err(format!("function `{}` takes 2 arguments", name)),
+        },
+        _ => return Err(format!("unknown function `{}`", name)),
+    })
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(expr: &str) -> Result<Value, ConfigError> {
+        eval("k", expr, &mut |key: &str| match key {
+            "server.port" => Ok(Value::Int(8080)),
+            "env" => Ok(Value::Str("prod".to_string())),
+            "max-size" => Ok(Value::Float(1.5)),
+            "version" => Ok(Value::infer("1.10".to_string())),
+            "zip" => Ok(Value::infer("007".to_string())),
+            _ => Err(ConfigError::ConfigRecursiveNotFound(key.to_string())),
+        })
+    }
+
+    fn ok(expr: &str) -> String {
+        run(expr).unwrap().to_string()
+    }
+
+    fn err(expr: &str) -> String {
+        match run(expr) {
+            Err(ConfigError::ConfigParseError(key, msg)) => {
+                assert_eq!("k", key);
+                msg.strip_suffix(&format!(" in `{}`", expr))
+                    .unwrap()
+                    .to_string()
+            }
+            v => panic!("unexpected {:?}", v),
+        }
+    }
+
+    #[test]
+    fn eval_test() {
+        assert_eq!("8081", ok("server.port + 1"));
+        assert_eq!("7", ok("1 + 2 * 3"));
+        assert_eq!("9", ok("(1 + 2) * 3"));
+        assert_eq!("3", ok("max-size * 2"));
+        assert_eq!("-0.5", ok("max-size - 2"));
+        assert_eq!("2", ok("7 / 3"));
+        assert_eq!("1", ok("7 % 3"));
+        assert_eq!("3.5", ok("7 / 2.0"));
+        assert_eq!("-8080", ok("-server.port"));
+        assert_eq!("warn", ok("env == 'prod' ? 'warn' : 'debug'"));
+        assert_eq!("debug", ok("env != \"prod\" ? 'warn' : 'debug'"));
+        assert_eq!("b", ok("1 > 2 ? 'a' : 2 > 1 ? 'b' : 'c'"));
+        assert_eq!("true", ok("1 < 2 && 2 <= 2 && !(3 >= 4) || false"));
+        assert_eq!("true", ok("1 == 1.0"));
+        assert_eq!("true", ok("'a' < 'b'"));
+        assert_eq!("host:8080", ok("'host:' + server.port"));
+        assert_eq!("1.10-beta", ok("version + '-beta'"));
+        assert_eq!("z007", ok("'z' + zip"));
+        assert_eq!("007", ok("str(zip)"));
+        assert_eq!("8", ok("zip + 1"));
+        assert_eq!("2.2", ok("version * 2"));
+        assert_eq!("true", ok("zip == 7"));
+        assert_eq!("it's", ok("'it\\'s'"));
+        assert_eq!("PROD", ok("upper(env)"));
+        assert_eq!("prod", ok("lower('PROD')"));
+        assert_eq!("a", ok("trim(' a ')"));
+        assert_eq!("4", ok("len(env)"));
+        assert_eq!("prod-8080", ok("concat(env, '-', server.port)"));
+        assert_eq!("pr0d", ok("replace(env, 'o', '0')"));
+        assert_eq!("ro", ok("substr(env, 1, 2)"));
+        assert_eq!("od", ok("substr(env, 2)"));
+        assert_eq!(
+            "true",
+            ok("contains(env, 'ro') && starts_with(env, 'p') && ends_with(env, 'd')")
+        );
+        assert_eq!("81", ok("int('80') + 1"));
+        assert_eq!("2", ok("int(2.0)"));
+        assert_eq!("2.5", ok("float('1.5') + 1"));
+        assert_eq!("80801", ok("str(server.port) + 1"));
+        assert_eq!("5", ok("abs(-5)"));
+        assert_eq!("1", ok("min(1, 2)"));
+        assert_eq!("2", ok("max(1, 2)"));
+        // Missing keys in branches not taken are not read.
+        assert_eq!("a", ok("true ? 'a' : missing"));
+        assert_eq!("false", ok("false && missing"));
+    }
+
+    #[test]
+    fn eval_error_test() {
+        assert_eq!("division by zero", err("1 / 0"));
+        assert_eq!("division by zero", err("1.0 % 0"));
+        assert_eq!("integer overflow", err("9223372036854775807 + 1"));
+        assert_eq!("cannot apply `-` to string and int", err("env - 1"));
+        assert_eq!("cannot apply `<` to string and int", err("env < 1"));
+        assert_eq!("cannot apply `-` to string", err("-env"));
+        assert_eq!("expected bool, found int", err("!1"));
+        assert_eq!("expected bool, found int", err("1 ? 2 : 3"));
+        assert_eq!("expected `:`, found end", err("true ? 1"));
+        assert_eq!("unexpected `)`", err("1 + )"));
+        assert_eq!("unexpected 2", err("1 2"));
+        assert_eq!("unexpected end", err("1 +"));
+        assert_eq!("unterminated string", err("'abc"));
+        assert_eq!("unexpected char `#`", err("1 # 2"));
+        assert_eq!("unknown function `exec`", err("exec('rm')"));
+        assert_eq!("function `upper` takes 1 argument", err("upper()"));
+        assert_eq!("cannot convert 'x' to int", err("int('x')"));
+        assert_eq!(
+            "cannot convert 1.5 to int without loss",
+            err("int(max-size)")
+        );
+        assert_eq!(
+            "cannot convert 10000000000000000000 to int without loss",
+            err("int(10000000000000000000.0)")
+        );
+        assert_eq!("expression is too deep", err(&"(".repeat(100)));
+        assert!(matches!(
+            run("missing + 1"),
+            Err(ConfigError::ConfigRecursiveNotFound(k)) if k == "missing"
+        ));
+    }
+
+    #[test]
+    fn is_expression_test() {
+        assert!(!is_expression("server.port"));
+        assert!(!is_expression("a.b[0]"));
+        assert!(!is_expression("max-size"));
+        assert!(!is_expression("key:default value"));
+        assert!(!is_expression("env:HOME"));
+        assert!(!is_expression("@db.primary"));
+        assert!(is_expression("server.port + 1"));
+        assert!(is_expression("env == 'prod' ? 'warn' : 'debug'"));
+        assert!(is_expression("upper(name)"));
+        assert!(is_expression("!flag"));
+    }
+
+    #[test]
+    fn infer_test() {
+        assert_eq!(
+            Value::Num("1".to_string(), Box::new(Value::Int(1))),
+            Value::infer("1".to_string())
+        );
+        assert_eq!(
+            Value::Num("1.50".to_string(), Box::new(Value::Float(1.5))),
+            Value::infer("1.50".to_string())
+        );
+        assert_eq!(Value::Bool(true), Value::infer("true".to_string()));
+        assert_eq!(
+            Value::Str("inf".to_string()),
+            Value::infer("inf".to_string())
+        );
+        assert_eq!(
+            Value::Str("1.2.3".to_string()),
+            Value::infer("1.2.3".to_string())
+        );
+        assert_eq!(Value::Str("a".to_string()), Value::infer("a".to_string()));
+    }
+}
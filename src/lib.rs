@@ -28,17 +28,31 @@ extern crate quickcheck_macros;
 
 mod cache;
 mod configuration;
+#[cfg(feature = "serde")]
+mod de;
 mod derive;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
 mod err;
+mod export;
+mod expr;
 mod key;
 
 mod prelude;
+mod provenance;
+mod render;
+mod resolver;
+#[cfg(feature = "zeroize")]
+mod secret;
 pub mod source;
+mod to_config;
+mod tree;
 pub mod validate;
 mod value;
 mod value_ref;
 
 use key::PartialKeyCollector;
+use source::ConfigSourceBuilder;
 
 /// Automatic derive [`FromConfig`] instance.
 ///
@@ -77,6 +91,22 @@ use key::PartialKeyCollector;
 /// }
 /// ```
 ///
+/// # Rename Annotation Attribute
+///
+/// * `#[config(rename_all = "kebab-case")]`
+///
+/// This attr renames all fields without `#[config(name = "...")]`, supported rules are
+/// `lowercase`, `UPPERCASE`, `PascalCase`, `camelCase`, `snake_case`, `SCREAMING_SNAKE_CASE`,
+/// `kebab-case` and `SCREAMING-KEBAB-CASE`.
+///
+/// ```ignore,rust
+/// #[derive(FromConfig)]
+/// #[config(rename_all = "kebab-case")]
+/// struct Test {
+///   max_size: u8, // This field uses partial key `max-size`.
+/// }
+/// ```
+///
 /// # Field Annotation Attribute
 ///
 /// * `#[config(name = "val")]`
@@ -104,20 +134,72 @@ use key::PartialKeyCollector;
 ///   enabled_with_default: bool, // This field has default value `true`.
 /// }
 /// ```
+///
+/// * `#[config(secret)]`
+///
+/// This attr hides raw value of underlying field from parse errors, fields with type `Secret` (feature **zeroize**) are always treated as secret.
+///
+/// ```ignore,rust
+/// #[derive(FromConfig)]
+/// struct Test {
+///   #[config(secret)]
+///   token: String, // Parse errors of this field print `***` instead of its value.
+/// }
+/// ```
+///
+/// * `#[config(skip)]`
+///
+/// This attr skips underlying field, it is always initialized by [`Default`].
+///
+/// ```ignore,rust
+/// #[derive(FromConfig)]
+/// struct Test {
+///   #[config(skip)]
+///   cache: Vec<u8>, // This field is not read from config.
+/// }
+/// ```
 pub use cfg_derive::FromConfig;
-pub use configuration::{ConfigContext, Configuration, PredefinedConfigurationBuilder};
+/// Automatic derive [`ToConfig`] instance.
+///
+/// It accepts the same attributes as [FromConfig](./derive.FromConfig.html), so derived instances round-trip.
+/// Fields are written under `#[config(name = "...")]` or names produced by `#[config(rename_all = "...")]`,
+/// fields with `#[config(skip)]` are not written, other attributes are ignored.
+///
+/// ```ignore,rust
+/// #[derive(FromConfig, ToConfig)]
+/// #[config(rename_all = "kebab-case")]
+/// struct Test {
+///   max_size: u8,
+///   #[config(skip)]
+///   cache: Vec<u8>,
+/// }
+/// ```
+pub use cfg_derive::ToConfig;
+pub use configuration::{
+    ArrayMerge, ConfigContext, Configuration, PredefinedConfigurationBuilder, Stage,
+};
 pub use derive::FromConfigWithPrefix;
-pub use err::ConfigError;
+#[cfg(feature = "diagnostics")]
+#[cfg_attr(docsrs, doc(cfg(feature = "diagnostics")))]
+pub use diagnostics::Report;
 pub(crate) use err::ConfigLock;
+pub use err::{ConfigError, ErrorContext, UnusedKeys};
+pub use export::Format;
 pub use key::ConfigKey;
 pub use prelude::*;
+pub use provenance::{Definition, Location, Provenance};
+pub use resolver::PlaceholderResolver;
+#[cfg(feature = "zeroize")]
+#[cfg_attr(docsrs, doc(cfg(feature = "zeroize")))]
+pub use secret::Secret;
+pub use tree::ConfigTree;
 #[allow(unused_imports)]
 #[cfg(feature = "log")]
 pub use value::log as _;
 #[allow(unused_imports)]
 #[cfg(feature = "coarsetime")]
 pub use value::time as _;
-pub use value::{ConfigValue, FromStrHolder, FromStringValue, FromValue};
+pub use value::{CoercionPolicy, ConfigValue, FromStrHolder, FromStringValue, FromValue};
 pub use value_ref::RefValue;
 
 #[doc(hidden)]
@@ -170,3 +252,30 @@ pub trait FromConfig: Sized {
         value: Option<ConfigValue<'_>>,
     ) -> Result<Self, ConfigError>;
 }
+
+/// Write config instance back into config keys, the reverse of [`FromConfig`].
+///
+/// Values written by [`ToConfig`] can be read back by [`FromConfig`], so effective settings can be
+/// dumped, diffed, or used to seed another [`Configuration`].
+/// This trait can be automatically derived, please refer to [Derive ToConfig](./derive.ToConfig.html) for details.
+///
+/// ```rust
+/// use cfg_rs::*;
+/// #[derive(Debug, PartialEq, FromConfig, ToConfig)]
+/// struct App {
+///     name: String,
+///     ports: Vec<u16>,
+/// }
+/// let app = App { name: "demo".to_string(), ports: vec![80, 443] };
+/// let config = Configuration::new()
+///     .register_kv("app")
+///     .set_config("app", &app)
+///     .unwrap()
+///     .finish()
+///     .unwrap();
+/// assert_eq!(app, config.get::<App>("app").unwrap());
+/// ```
+pub trait ToConfig {
+    /// Write config into builder under current key.
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError>;
+}
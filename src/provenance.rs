@@ -0,0 +1,140 @@
+//! Provenance of config values.
+use std::{
+    cell::RefCell,
+    fmt::{Display, Formatter},
+    path::PathBuf,
+};
+
+use crate::ConfigValue;
+
+/// Location of a value in config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// File path.
+    pub path: PathBuf,
+    /// Line number, starts from 1, if the file format reports it.
+    pub line: Option<usize>,
+    /// Column number, starts from 1, if the file format reports it.
+    pub column: Option<usize>,
+}
+
+impl Location {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            line: None,
+            column: None,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Value of a key defined by one source.
+#[derive(Debug, Clone)]
+pub struct Definition {
+    /// Source name, see [`ConfigSource::name`](source/trait.ConfigSource.html#tymethod.name).
+    pub source: String,
+    /// Raw value before placeholders expanded.
+    pub value: ConfigValue<'static>,
+    /// Location of value, only available for file sources.
+    pub location: Option<Location>,
+}
+
+/// Provenance of a key, see [`Configuration::explain`](struct.Configuration.html#method.explain).
+///
+/// ```rust
+/// use cfg_rs::*;
+/// let config = Configuration::new()
+///     .register_kv("env")
+///     .set("app.url", "http://${app.host}")
+///     .finish()
+///     .unwrap()
+///     .register_kv("file")
+///     .set("app.url", "http://localhost")
+///     .set("app.host", "example.com")
+///     .finish()
+///     .unwrap();
+/// let p = config.explain("app.url");
+/// assert_eq!("env", p.winner().unwrap().source);
+/// assert_eq!(2, p.definitions.len());
+/// assert_eq!(Some("http://example.com"), p.resolved.as_deref());
+/// assert_eq!(vec![("app.host".to_string(), "example.com".to_string())], p.expansions);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Provenance {
+    /// Config key.
+    pub key: String,
+    /// All sources defining the key, from highest priority to lowest, the first one wins.
+    pub definitions: Vec<Definition>,
+    /// Value after placeholders expanded, `None` if key is not found or cannot be read as string.
+    pub resolved: Option<String>,
+    /// Placeholders expanded when resolving value, as key and expanded text, in resolving order.
+    pub expansions: Vec<(String, String)>,
+}
+
+impl Provenance {
+    /// Definition which supplies the value.
+    pub fn winner(&self) -> Option<&Definition> {
+        self.definitions.first()
+    }
+}
+
+impl Display for Provenance {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key)?;
+        match &self.resolved {
+            Some(v) => writeln!(f, " = {}", v)?,
+            None => writeln!(f, " is not resolved")?,
+        }
+        for (i, d) in self.definitions.iter().enumerate() {
+            write!(
+                f,
+                "  {} {}: {:?}",
+                if i == 0 { "*" } else { "-" },
+                d.source,
+                d.value
+            )?;
+            if let Some(l) = &d.location {
+                write!(f, " at {}", l)?;
+            }
+            writeln!(f)?;
+        }
+        for (k, v) in &self.expansions {
+            writeln!(f, "  ${{{}}} => {}", k, v)?;
+        }
+        Ok(())
+    }
+}
+
+thread_local! {
+    static EXPANSIONS: RefCell<Option<Vec<(String, String)>>> = const { RefCell::new(None) };
+}
+
+/// Record placeholder expansion, if tracing.
+pub(crate) fn trace_expansion(key: &str, value: &str) {
+    EXPANSIONS.with(|e| {
+        if let Some(e) = e.borrow_mut().as_mut() {
+            e.push((key.to_string(), value.to_string()));
+        }
+    });
+}
+
+/// Run `f` and collect placeholder expansions during it.
+pub(crate) fn with_trace<T, F: FnOnce() -> T>(f: F) -> (T, Vec<(String, String)>) {
+    let prev = EXPANSIONS.with(|e| e.borrow_mut().replace(vec![]));
+    let v = f();
+    let trace = EXPANSIONS.with(|e| std::mem::replace(&mut *e.borrow_mut(), prev));
+    (v, trace.unwrap_or_default())
+}
@@ -0,0 +1,393 @@
+//! Text templates, see [`Configuration::render`](struct.Configuration.html#method.render).
+use std::mem::take;
+
+use crate::ConfigError;
+
+const FOR_START: &str = "${#for";
+const FOR_END: &str = "${/for}";
+
+/// Node of template.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Node {
+    /// Text with placeholders.
+    Text(String),
+    /// Block of `${#for index, item in key}` ... `${/for}`.
+    For {
+        index: Option<String>,
+        item: String,
+        key: String,
+        body: Vec<Node>,
+    },
+}
+
+/// Loop variable, bound to config key of item, or to index of array or name of map.
+#[derive(Debug)]
+pub(crate) enum Binding {
+    Key(String),
+    Literal(String),
+}
+
+/// Loop header, with tag for errors.
+struct Header<'a> {
+    tag: &'a str,
+    index: Option<String>,
+    item: String,
+    key: String,
+}
+
+fn is_name(v: &str) -> bool {
+    v.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && v.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn parse_header(tag: &str) -> Result<Header<'_>, ConfigError> {
+    let err = || {
+        ConfigError::ConfigParseError(
+            tag.to_string(),
+            "invalid loop, expected `${#for item in key}` or `${#for index, item in key}`"
+                .to_string(),
+        )
+    };
+    let (vars, key) = tag
+        .strip_prefix(FOR_START)
+        .and_then(|v| v.strip_suffix('}'))
+        .and_then(|v| v.split_once(" in "))
+        .ok_or_else(err)?;
+    let vars: Vec<&str> = vars.split(',').map(str::trim).collect();
+    let key = key.trim();
+    if key.is_empty() || !vars.iter().all(|v| is_name(v)) {
+        return Err(err());
+    }
+    let (index, item) = match vars.as_slice() {
+        [item] => (None, item),
+        [index, item] => (Some(index.to_string()), item),
+        _ => return Err(err()),
+    };
+    Ok(Header {
+        tag,
+        index,
+        item: item.to_string(),
+        key: key.to_string(),
+    })
+}
+
+/// Parse template into text and loop blocks, loop tags standing on their own lines are removed with the lines.
+pub(crate) fn parse(template: &str) -> Result<Vec<Node>, ConfigError> {
+    // Open loops with their nodes, the first one is root.
+    let mut stack: Vec<(Option<Header<'_>>, Vec<Node>)> = vec![(None, vec![])];
+    let mut text = String::new();
+    // Text starts from beginning of line.
+    let mut line_start = true;
+    let mut rest = template;
+    while let Some(pos) = rest.find(['\\', '$']) {
+        text.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('\\') {
+            let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            text.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        let start =
+            rest.starts_with(FOR_START) && rest[FOR_START.len()..].starts_with(char::is_whitespace);
+        if !start && !rest.starts_with(FOR_END) {
+            text.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+        let end = rest.find('}').map_or(rest.len(), |p| p + 1);
+        let tag = &rest[..end];
+        rest = &rest[end..];
+
+        let line = match text.rfind('\n') {
+            Some(p) => Some(p + 1),
+            None if line_start => Some(0),
+            _ => None,
+        };
+        let after = rest.find('\n').map_or(rest.len(), |p| p + 1);
+        line_start = false;
+        if let Some(line) = line {
+            if text[line..].trim().is_empty() && rest[..after].trim().is_empty() {
+                text.truncate(line);
+                rest = &rest[after..];
+                line_start = true;
+            }
+        }
+        if !text.is_empty() {
+            if let Some((_, nodes)) = stack.last_mut() {
+                nodes.push(Node::Text(take(&mut text)));
+            }
+        }
+
+        if start {
+            stack.push((Some(parse_header(tag)?), vec![]));
+            continue;
+        }
+        match (stack.pop(), stack.last_mut()) {
+            (Some((Some(h), body)), Some((_, nodes))) => nodes.push(Node::For {
+                index: h.index,
+                item: h.item,
+                key: h.key,
+                body,
+            }),
+            _ => {
+                return Err(ConfigError::ConfigParseError(
+                    FOR_END.to_string(),
+                    "no loop to close".to_string(),
+                ));
+            }
+        }
+    }
+    text.push_str(rest);
+    let (header, mut nodes) = stack.pop().unwrap_or_default();
+    if let Some(h) = header {
+        return Err(ConfigError::ConfigParseError(
+            h.tag.to_string(),
+            format!("loop is not closed by `{}`", FOR_END),
+        ));
+    }
+    if !text.is_empty() {
+        nodes.push(Node::Text(text));
+    }
+    Ok(nodes)
+}
+
+/// Check if `rest` starts with loop variable `name`, rather than a key with prefix `name`.
+fn is_bound(rest: &str, name: &str) -> bool {
+    rest.strip_prefix(name)
+        .is_some_and(|v| !v.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '-'))
+}
+
+/// Replace loop variable at the beginning of `key`.
+fn bind_key(key: &str, bindings: &[(String, Binding)]) -> String {
+    match bindings.iter().rev().find(|(n, _)| is_bound(key, n)) {
+        Some((n, Binding::Key(target))) => format!("{}{}", target, &key[n.len()..]),
+        _ => key.to_string(),
+    }
+}
+
+/// Replace loop variables at the beginning of placeholders, `${item.key}` is replaced by `${target.key}`,
+/// and `${index}` is replaced by escaped index or name.
+fn bind(text: &str, bindings: &[(String, Binding)]) -> String {
+    if bindings.is_empty() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['\\', '$']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if rest.starts_with('\\') {
+            let len = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+            out.push_str(&rest[..len]);
+            rest = &rest[len..];
+            continue;
+        }
+        if !rest.starts_with("${") {
+            out.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+        rest = &rest[2..];
+        match bindings.iter().rev().find(|(n, _)| is_bound(rest, n)) {
+            Some((n, Binding::Key(target))) => {
+                out.push_str("${");
+                out.push_str(target);
+                rest = &rest[n.len()..];
+            }
+            Some((n, Binding::Literal(v))) if rest[n.len()..].starts_with('}') => {
+                for c in v.chars() {
+                    if matches!(c, '\\' | '$' | '}') {
+                        out.push('\\');
+                    }
+                    out.push(c);
+                }
+                rest = &rest[n.len() + 1..];
+            }
+            _ => out.push_str("${"),
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// End of placeholder at the beginning of `v`, nested placeholders are included.
+fn placeholder_end(v: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = v.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '$' if chars.peek().is_some_and(|(_, c)| *c == '{') => {
+                chars.next();
+                depth += 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Expand placeholders of text by `f`, `$` and `}` out of placeholders are kept, `\` escapes the next char.
+fn expand_text(
+    text: &str,
+    f: &mut dyn FnMut(&str) -> Result<String, ConfigError>,
+) -> Result<String, ConfigError> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(['\\', '$']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(v) = rest.strip_prefix('\\') {
+            let len = v.chars().next().map_or(0, char::len_utf8);
+            out.push_str(&v[..len]);
+            rest = &v[len..];
+        } else if rest.starts_with("${") {
+            // Unclosed placeholder is reported by `f`.
+            let end = placeholder_end(rest).unwrap_or(rest.len());
+            out.push_str(&f(&rest[..end])?);
+            rest = &rest[end..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Indexes or names of items under key, with keys of items.
+type Children<'a> = dyn FnMut(&str) -> Result<Vec<(String, String)>, ConfigError> + 'a;
+
+/// Render nodes into `out`.
+///
+/// * `expand` - Expand placeholder.
+/// * `children` - Items under key.
+pub(crate) fn render(
+    nodes: &[Node],
+    bindings: &mut Vec<(String, Binding)>,
+    expand: &mut dyn FnMut(&str) -> Result<String, ConfigError>,
+    children: &mut Children<'_>,
+    out: &mut String,
+) -> Result<(), ConfigError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(&expand_text(&bind(text, bindings), expand)?),
+            Node::For {
+                index,
+                item,
+                key,
+                body,
+            } => {
+                for (name, target) in children(&bind_key(key, bindings))? {
+                    let mark = bindings.len();
+                    if let Some(index) = index {
+                        bindings.push((index.clone(), Binding::Literal(name)));
+                    }
+                    bindings.push((item.clone(), Binding::Key(target)));
+                    render(body, bindings, expand, children, out)?;
+                    bindings.truncate(mark);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(v: &str) -> Node {
+        Node::Text(v.to_string())
+    }
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(
+            vec![text("a ${b} $c \\${#for x in y}")],
+            parse("a ${b} $c \\${#for x in y}").unwrap()
+        );
+        assert_eq!(
+            vec![
+                text("upstream app {\n"),
+                Node::For {
+                    index: None,
+                    item: "s".to_string(),
+                    key: "app.servers".to_string(),
+                    body: vec![text("    server ${s.host};\n")],
+                },
+                text("}\n"),
+            ],
+            parse("upstream app {\n    ${#for s in app.servers}\n    server ${s.host};\n    ${/for}\n}\n")
+                .unwrap()
+        );
+        assert_eq!(
+            vec![
+                text("["),
+                Node::For {
+                    index: Some("i".to_string()),
+                    item: "v".to_string(),
+                    key: "a".to_string(),
+                    body: vec![text("${i}")],
+                },
+                text("]"),
+            ],
+            parse("[${#for i, v in a}${i}${/for}]").unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_error_test() {
+        for (template, key) in [
+            ("${#for x in a}", "${#for x in a}"),
+            ("${#for x a}${/for}", "${#for x a}"),
+            ("${#for 1 in a}${/for}", "${#for 1 in a}"),
+            ("${#for a, b, c in a}${/for}", "${#for a, b, c in a}"),
+            ("${#for x in }${/for}", "${#for x in }"),
+            ("${/for}", "${/for}"),
+        ] {
+            match parse(template) {
+                Err(ConfigError::ConfigParseError(k, _)) => assert_eq!(key, k),
+                v => panic!("unexpected {:?}", v),
+            }
+        }
+    }
+
+    #[test]
+    fn expand_text_test() {
+        let mut f = |v: &str| Ok(format!("<{}>", v));
+        assert_eq!(
+            "a { $b } <${c}> <${d:${e}}> ${f} \\",
+            expand_text("a { $b } ${c} ${d:${e}} \\${f} \\\\", &mut f).unwrap()
+        );
+        assert_eq!("<${a\\}}>", expand_text("${a\\}}", &mut f).unwrap());
+        assert_eq!("<${a>", expand_text("${a", &mut f).unwrap());
+    }
+
+    #[test]
+    fn bind_test() {
+        let bindings = vec![
+            ("i".to_string(), Binding::Literal("a}$".to_string())),
+            ("s".to_string(), Binding::Key("app.servers[0]".to_string())),
+        ];
+        assert_eq!(
+            "${app.servers[0].host} ${app.servers[0]} ${x:${app.servers[0].port}} ${server} ${s-x} a\\}\\$ ${i.x} \\${s}",
+            bind(
+                "${s.host} ${s} ${x:${s.port}} ${server} ${s-x} ${i} ${i.x} \\${s}",
+                &bindings
+            )
+        );
+        assert_eq!("app.servers[0].tags", bind_key("s.tags", &bindings));
+        assert_eq!("servers", bind_key("servers", &bindings));
+    }
+}
@@ -0,0 +1,84 @@
+//! Placeholder resolvers.
+use std::env::var;
+
+use crate::ConfigError;
+
+/// Resolve placeholder `${scheme:value}` by scheme, see [`Configuration::register_resolver`](struct.Configuration.html#method.register_resolver).
+///
+/// Built-in resolvers, registered by [`Configuration::register_builtin_resolvers`](struct.Configuration.html#method.register_builtin_resolvers):
+/// * `env` - Env variable, such as `${env:HOME}`.
+/// * `file` - File content with whitespaces trimmed, such as `${file:/run/secrets/db_password}`.
+/// * `base64` - Decoded base64 text, such as `${base64:aGVsbG8=}`.
+pub trait PlaceholderResolver: Send + Sync {
+    /// Resolve `value`, which is the text after `scheme:` with inner placeholders expanded.
+    fn resolve(&self, value: &str) -> Result<String, ConfigError>;
+}
+
+impl<F: Fn(&str) -> Result<String, ConfigError> + Send + Sync> PlaceholderResolver for F {
+    fn resolve(&self, value: &str) -> Result<String, ConfigError> {
+        (self)(value)
+    }
+}
+
+pub(crate) fn resolve_env(name: &str) -> Result<String, ConfigError> {
+    var(name).map_err(|_| ConfigError::ConfigRecursiveNotFound(format!("env:{}", name)))
+}
+
+pub(crate) fn resolve_file(path: &str) -> Result<String, ConfigError> {
+    std::fs::read_to_string(path)
+        .map(|v| v.trim().to_string())
+        .map_err(|e| ConfigError::ConfigParseError(format!("file:{}", path), e.to_string()))
+}
+
+pub(crate) fn resolve_base64(value: &str) -> Result<String, ConfigError> {
+    let err =
+        || ConfigError::ConfigParseError(format!("base64:{}", value), "invalid base64".into());
+    let mut buf = Vec::with_capacity(value.len() * 3 / 4);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    for c in value.trim_end_matches('=').bytes() {
+        let v = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return Err(err()),
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            buf.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    String::from_utf8(buf).map_err(ConfigError::from_cause)
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn base64_test() {
+        assert_eq!("hello", resolve_base64("aGVsbG8=").unwrap());
+        assert_eq!("hello!", resolve_base64("aGVsbG8h").unwrap());
+        assert_eq!("hi", resolve_base64("aGk").unwrap());
+        assert_eq!("", resolve_base64("").unwrap());
+        assert_eq!("?>", resolve_base64("Pz4=").unwrap());
+        assert_eq!("?>", resolve_base64("Pz4").unwrap());
+        assert!(resolve_base64("a*b").is_err());
+    }
+
+    #[test]
+    fn file_error_test() {
+        match resolve_file("target/resolver_not_exist.txt") {
+            Err(ConfigError::ConfigParseError(key, _)) => {
+                assert_eq!("file:target/resolver_not_exist.txt", key)
+            }
+            v => panic!("unexpected {:?}", v),
+        }
+    }
+}
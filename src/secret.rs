@@ -0,0 +1,153 @@
+//! Secret values.
+use std::fmt::{Debug, Display, Formatter};
+
+use zeroize::Zeroize;
+
+use crate::{
+    ConfigContext, ConfigError, ConfigValue, FromConfig, ToConfig, source::ConfigSourceBuilder,
+};
+
+/// Secret value, such as passwords or tokens, must enable feature **zeroize**.
+///
+/// * [`Debug`] and [`Display`] print `***`.
+/// * Errors of parsing secret never include its raw value.
+/// * Value is zeroized on drop by [`zeroize::Zeroize`].
+///
+/// Fields with type [`Secret`] are treated as `#[config(secret)]` by [derive](./derive.FromConfig.html).
+///
+/// ```rust
+/// use cfg_rs::*;
+/// #[derive(Debug, FromConfig)]
+/// struct Db {
+///     user: String,
+///     password: Secret<String>,
+/// }
+/// let config = Configuration::new()
+///     .register_kv("db")
+///     .set("db.user", "admin")
+///     .set("db.password", "p@ss")
+///     .finish()
+///     .unwrap();
+/// let db: Db = config.get("db").unwrap();
+/// assert_eq!("p@ss", db.password.expose());
+/// assert_eq!(r#"Db { user: "admin", password: *** }"#, format!("{:?}", db));
+/// ```
+///
+/// Notice that config sources still hold the raw value, only the parsed value is protected.
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    /// Create secret.
+    pub fn new(value: T) -> Self {
+        Secret(value)
+    }
+
+    /// Expose raw value, be careful not to leak it.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Secret(self.0.clone())
+    }
+}
+
+impl<T: Zeroize + PartialEq> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(value: T) -> Self {
+        Secret(value)
+    }
+}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl<T: Zeroize> Display for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("***")
+    }
+}
+
+impl<T: FromConfig + Zeroize> FromConfig for Secret<T> {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        T::from_config(context, value)
+            .map(Secret)
+            .map_err(|e| context.redact(e))
+    }
+}
+
+impl<T: ToConfig + Zeroize> ToConfig for Secret<T> {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.0.to_config(builder)
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{source::memory::HashSource, test::TestConfigExt};
+
+    #[derive(Debug, FromConfig)]
+    #[config(crate = "crate")]
+    struct Db {
+        #[config(secret)]
+        token: u16,
+        password: Option<Secret<u16>>,
+    }
+
+    #[test]
+    fn secret_display_test() {
+        let s = Secret::new("p@ss".to_string());
+        assert_eq!("***", format!("{}", s));
+        assert_eq!("***", format!("{:?}", s));
+        assert_eq!("p@ss", s.expose());
+        assert_eq!(s, s.clone());
+    }
+
+    #[test]
+    fn secret_error_test() {
+        let config = HashSource::new("test")
+            .set("db.token", "hunter2")
+            .set("db.password", "hunter2")
+            .set("placeholder.token", "${hunter2")
+            .set("ok.token", "1")
+            .set("ok.password", "2")
+            .new_config();
+        let err = config.get::<Db>("db").unwrap_err();
+        assert_eq!(
+            "Configuration parse error for key 'db.token': *** (source 'test', in Db.token)",
+            err.to_string()
+        );
+        let err = config.get::<Db>("placeholder").unwrap_err();
+        assert_eq!(
+            "Configuration parse error for key 'placeholder.token': *** (source 'test', in Db.token)",
+            err.to_string()
+        );
+        let err = config.get::<Secret<u16>>("db.password").unwrap_err();
+        assert!(!err.to_string().contains("hunter2"));
+        let db: Db = config.get("ok").unwrap();
+        assert_eq!(1, db.token);
+        assert_eq!(2, *db.password.unwrap().expose());
+        assert!(config.get::<Secret<String>>("db.password").is_ok());
+    }
+}
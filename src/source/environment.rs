@@ -1,24 +1,34 @@
 //! Environment sources.
 use std::env::vars;
 
-use crate::ConfigError;
+use crate::{ConfigError, ConfigValue};
 
 use super::{ConfigSource, memory::ConfigSourceBuilder};
 
 /// Prefixed environment source.
 #[derive(Debug)]
-pub(crate) struct PrefixEnvironment(String, String);
+pub(crate) struct PrefixEnvironment(String, String, Option<String>);
 
 impl ConfigSource for PrefixEnvironment {
     fn name(&self) -> &str {
         &self.1
     }
     fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        let mut nulls = vec![];
         for (k, v) in vars() {
             if let Some(kk) = k.strip_prefix(&self.0) {
-                builder.set(&kk.to_lowercase().replace('_', "."), v);
+                let kk = kk.to_lowercase().replace('_', ".");
+                if self.2.as_ref() == Some(&v) {
+                    nulls.push(kk);
+                } else {
+                    builder.set(&kk, v);
+                }
             }
         }
+        // Values of the same source are kept.
+        for k in nulls {
+            builder.set(&k, ConfigValue::Null);
+        }
         Ok(())
     }
 }
@@ -29,7 +39,18 @@ impl PrefixEnvironment {
     pub(crate) fn new(prefix: &str) -> Self {
         let n = format!("{}_", prefix.to_uppercase());
         let nm = format!("prefix_env:{}**", n);
-        Self(n, nm)
+        Self(n, nm, None)
+    }
+
+    /// Env variable prefix, such as `CFG_`.
+    pub(crate) fn prefix(&self) -> &str {
+        &self.0
+    }
+
+    /// Treat env variables with value `marker` as [`ConfigValue::Null`].
+    pub(crate) fn with_null(mut self, marker: &str) -> Self {
+        self.2 = Some(marker.to_string());
+        self
     }
 }
 
@@ -38,7 +59,7 @@ impl PrefixEnvironment {
 mod test {
     use std::{collections::HashMap, env::set_var};
 
-    use crate::test::TestConfigExt;
+    use crate::{Configuration, test::TestConfigExt};
 
     use super::*;
 
@@ -106,4 +127,25 @@ mod test {
         let map: HashMap<String, String> = HashMap::new();
         assert_eq!(map, value.unwrap());
     }
+
+    #[test]
+    fn env_null_test() {
+        unsafe {
+            set_var("NULLENV_A", "");
+            set_var("NULLENV_B", "b");
+        }
+        let config = Configuration::new()
+            .register_prefix_env_with_null("nullenv", "")
+            .unwrap()
+            .register_kv("low")
+            .set("a", "low")
+            .set("b", "low")
+            .finish()
+            .unwrap();
+        assert_eq!(None, config.get::<Option<String>>("a").unwrap());
+        assert_eq!("b", config.get::<String>("b").unwrap());
+
+        let config = PrefixEnvironment::new("nullenv").new_config();
+        assert_eq!("", config.get::<String>("a").unwrap());
+    }
 }
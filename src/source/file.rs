@@ -5,13 +5,22 @@ use std::{
     time::SystemTime,
 };
 
-use crate::{ConfigError, Mutex, err::ConfigLock};
+use crate::{ConfigError, Mutex, err::ConfigLock, export::glob_match};
 
 use super::{
-    ConfigSource, ConfigSourceAdaptor, ConfigSourceParser,
+    ConfigSource, ConfigSourceAdaptor, ConfigSourceParser, PROFILE_KEY, is_supported,
     memory::{ConfigSourceBuilder, HashSource},
 };
 
+/// Documents of file to load, see [`ConfigSourceParser::split_profiles`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Section {
+    /// Documents always active, profile sections are skipped.
+    Base,
+    /// Documents activated by profile.
+    Profile(String),
+}
+
 /// FileLoader
 #[derive(Debug)]
 pub(crate) struct FileLoader<L: ConfigSourceParser> {
@@ -19,6 +28,7 @@ pub(crate) struct FileLoader<L: ConfigSourceParser> {
     path: PathBuf,
     ext: bool,
     required: bool,
+    section: Section,
     modified: Mutex<Option<SystemTime>>,
     _data: PhantomData<L>,
 }
@@ -40,20 +50,65 @@ impl<L: ConfigSourceParser> FileLoader<L> {
             path,
             ext,
             required,
+            section: Section::Base,
             _data: PhantomData,
         }
     }
+
+    /// Load only documents of section, source name of profile section shows the profile.
+    #[cfg(any(feature = "toml", feature = "yaml", feature = "json", feature = "ini"))]
+    pub(crate) fn with_section(mut self, section: Section) -> Self {
+        if let Section::Profile(profile) = &section {
+            self.name = format!("{} (profile {})", self.name, profile);
+        }
+        self.section = section;
+        self
+    }
 }
 
 fn load_path<L: ConfigSourceParser>(
+    name: &str,
     path: PathBuf,
+    section: &Section,
     flag: &mut bool,
     builder: &mut ConfigSourceBuilder<'_>,
 ) -> Result<(), ConfigError> {
     if path.exists() {
         *flag = false;
-        let c = std::fs::read_to_string(&path).map_err(ConfigError::from_cause)?;
-        L::parse_source(&c)?.convert_source(builder)?;
+        let c = std::fs::read_to_string(&path)
+            .map_err(|e| ConfigError::from_cause(e).with_file(name, &path))?;
+        let v = L::parse_source(&c)
+            .map_err(|e| e.with_file(name, &path))
+            .and_then(|s| {
+                builder.set_file(Some(path));
+                for (profile, s) in L::split_profiles(s) {
+                    let active = match section {
+                        Section::Profile(p) => profile.as_ref() == Some(p),
+                        _ => profile.is_none(),
+                    };
+                    if active {
+                        s.convert_source(builder)?;
+                    }
+                }
+                Ok(())
+            });
+        if v.is_ok() {
+            // Values of profile sections are located under `profile.<name>`.
+            let prefix = match section {
+                Section::Profile(p) => format!(".{}.{}", PROFILE_KEY, p),
+                _ => String::new(),
+            };
+            for (key, line, column) in L::locate(&c) {
+                if let Some(key) = key
+                    .strip_prefix(prefix.as_str())
+                    .filter(|k| k.is_empty() || k.starts_with('.'))
+                {
+                    builder.set_location(key, line, column);
+                }
+            }
+        }
+        builder.set_file(None);
+        v?;
     }
     Ok(())
 }
@@ -66,12 +121,18 @@ impl<L: ConfigSourceParser> ConfigSource for FileLoader<L> {
     fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
         let mut flag = self.required;
         if self.ext {
-            load_path::<L>(self.path.clone(), &mut flag, builder)?;
+            load_path::<L>(
+                &self.name,
+                self.path.clone(),
+                &self.section,
+                &mut flag,
+                builder,
+            )?;
         } else {
             for ext in L::file_extensions() {
                 let mut path = self.path.clone();
                 path.set_extension(ext);
-                load_path::<L>(path, &mut flag, builder)?;
+                load_path::<L>(&self.name, path, &self.section, &mut flag, builder)?;
             }
         }
         if flag {
@@ -93,12 +154,75 @@ impl<L: ConfigSourceParser> ConfigSource for FileLoader<L> {
     }
 }
 
+/// Directory of drop-in fragments, such as `conf.d/*.toml`, see [`Configuration::register_dir`](crate::Configuration::register_dir).
+///
+/// Each file is registered as a source of its own, files added or removed are detected when refreshing.
+pub(crate) struct DirLoader {
+    name: String,
+    dir: PathBuf,
+    pattern: String,
+    /// Registered files, in lexical order.
+    pub(crate) files: Vec<PathBuf>,
+}
+
+impl DirLoader {
+    pub(crate) fn new(dir: PathBuf, pattern: &str) -> Self {
+        Self {
+            name: format!("dir:{}", dir.join(pattern).display()),
+            dir,
+            pattern: pattern.to_string(),
+            files: vec![],
+        }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Files in directory in lexical order, files of unsupported formats are skipped.
+    pub(crate) fn list(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut files = list_files(&self.dir, &self.pattern)?;
+        files.retain(|f| is_supported(f));
+        Ok(files)
+    }
+}
+
+/// Files in `dir` matching `pattern` in lexical order, `*` matches any characters of file name.
+///
+/// Pattern may start with sub directories, such as `conf.d/*.toml`, missing directory matches nothing.
+pub(crate) fn list_files(dir: &Path, pattern: &str) -> Result<Vec<PathBuf>, ConfigError> {
+    let (dir, pattern) = match pattern.rsplit_once('/') {
+        Some((sub, pattern)) => (dir.join(sub), pattern),
+        None => (dir.to_path_buf(), pattern),
+    };
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut files = vec![];
+    for entry in std::fs::read_dir(&dir).map_err(ConfigError::from_cause)? {
+        let path = entry.map_err(ConfigError::from_cause)?.path();
+        let matched = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| glob_match(pattern, name));
+        if matched && path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
 #[doc(hidden)]
 pub fn inline_source_config<S: ConfigSourceParser>(
     name: String,
     content: &'static str,
 ) -> Result<HashSource, ConfigError> {
-    let v = S::parse_source(content)?;
+    let v = S::parse_source(content).map_err(|e| {
+        e.with_context(|c| {
+            c.source.get_or_insert_with(|| name.clone());
+        })
+    })?;
     let mut m = HashSource::new(name);
     v.convert_source(&mut m.prefixed())?;
     Ok(m)
@@ -185,6 +309,24 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn list_files_test() -> Result<(), ConfigError> {
+        let dir: PathBuf = "target/list_files_test".into();
+        std::fs::create_dir_all(dir.join("conf.d")).map_err(ConfigError::from_cause)?;
+        for name in ["20-b.toml", "10-a.toml", "readme.md"] {
+            File::create(dir.join("conf.d").join(name)).map_err(ConfigError::from_cause)?;
+        }
+        std::fs::create_dir_all(dir.join("conf.d/30-c.toml")).map_err(ConfigError::from_cause)?;
+        assert_eq!(
+            vec![dir.join("conf.d/10-a.toml"), dir.join("conf.d/20-b.toml")],
+            super::list_files(&dir, "conf.d/*.toml")?
+        );
+        assert_eq!(3, super::list_files(&dir.join("conf.d"), "*")?.len());
+        assert!(super::list_files(&dir, "none/*.toml")?.is_empty());
+        std::fs::remove_dir_all(dir).map_err(ConfigError::from_cause)?;
+        Ok(())
+    }
+
     #[test]
     fn inline_source_config_success() {
         // 使用 Temp 解析器，内容无所谓
@@ -215,7 +357,10 @@ mod test {
             }
         }
         let result = super::inline_source_config::<Bad>("bad".to_string(), "abc");
-        assert!(matches!(result, Err(ConfigError::ConfigParseError(_, _))));
+        assert!(matches!(
+            result.as_ref().map_err(ConfigError::kind),
+            Err(ConfigError::ConfigParseError(_, _))
+        ));
     }
 
     #[test]
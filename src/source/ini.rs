@@ -35,11 +35,21 @@ impl ConfigSourceParser for Ini {
 #[cfg_attr(coverage_nightly, coverage(off))]
 #[cfg(test)]
 mod test {
-    use crate::{inline_source, test::source_test_suit, ConfigError};
+    use super::*;
+    use crate::{
+        inline_source,
+        inline_source_config,
+        test::{replace_test_suit, source_test_suit},
+    };
 
     #[test]
     #[allow(unused_qualifications)]
     fn inline_test() -> Result<(), ConfigError> {
         source_test_suit(inline_source!("../../app.ini")?)
     }
+
+    #[test]
+    fn replace_test() -> Result<(), ConfigError> {
+        replace_test_suit(inline_source_config::<Ini>("ini".into(), "[a]\n_replace = true\nx = x\n[b]\nx = x")?)
+    }
 }
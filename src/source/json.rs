@@ -1,24 +1,46 @@
 //! Json config source.
 
-use super::{memory::ConfigSourceBuilder, ConfigSourceAdaptor, ConfigSourceParser};
-use crate::ConfigError;
-use json::JsonValue;
+use super::{
+    line_column, memory::ConfigSourceBuilder, ConfigSourceAdaptor, ConfigSourceParser, PROFILE_KEY,
+};
+use crate::{ConfigError, ConfigValue};
+use json::{number::Number, JsonValue};
 
 pub type Json = JsonValue;
 
+/// Keep integers as [`ConfigValue::Int`] or [`ConfigValue::BigInt`], and fractions as [`ConfigValue::Float`]
+/// if [`f64`] holds them exactly, otherwise as [`ConfigValue::Decimal`].
+fn convert_number(v: Number) -> ConfigValue<'static> {
+    let (positive, mantissa, exponent) = v.as_parts();
+    if exponent >= 0 {
+        if let Some(i) = 10i128
+            .checked_pow(exponent as u32)
+            .and_then(|e| e.checked_mul(mantissa as i128))
+        {
+            return if positive { i } else { -i }.into();
+        }
+    } else if mantissa < 1_000_000_000_000_000 {
+        // Decimals with at most 15 significant digits round-trip through f64.
+        if let Ok(f) = v.to_string().parse() {
+            return ConfigValue::Float(f);
+        }
+    }
+    ConfigValue::Decimal(v.to_string())
+}
+
 impl ConfigSourceAdaptor for Json {
     fn convert_source(self, source: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
         match self {
             JsonValue::String(v) => source.insert(v),
             JsonValue::Short(v) => source.insert(v.as_str().to_string()),
-            JsonValue::Number(v) => source.insert(v.to_string()),
+            JsonValue::Number(v) => source.insert(convert_number(v)),
             JsonValue::Boolean(v) => source.insert(v),
             JsonValue::Array(v) => source.insert_array(v)?,
             JsonValue::Object(mut v) => source.insert_map(
                 v.iter_mut()
                     .map(|(k, v)| (k, std::mem::replace(v, JsonValue::Null))),
             )?,
-            JsonValue::Null => {}
+            JsonValue::Null => source.insert(ConfigValue::Null),
         }
         Ok(())
     }
@@ -27,22 +49,238 @@ impl ConfigSourceAdaptor for Json {
 impl ConfigSourceParser for Json {
     type Adaptor = Json;
     fn parse_source(content: &str) -> Result<Self::Adaptor, ConfigError> {
-        json::parse(content).map_err(ConfigError::from_cause)
+        json::parse(content).map_err(|e| match e {
+            json::Error::UnexpectedCharacter { line, column, .. } => {
+                ConfigError::from_cause_at(e, line, column)
+            }
+            e => ConfigError::from_cause(e),
+        })
     }
 
     fn file_extensions() -> Vec<&'static str> {
         vec!["json"]
     }
+
+    fn split_profiles(mut v: Json) -> Vec<(Option<String>, Json)> {
+        let mut out = vec![];
+        if let JsonValue::Object(o) = &mut v {
+            if let Some(JsonValue::Object(profiles)) = o.get_mut(PROFILE_KEY) {
+                // Entries other than objects are kept, so that they are reported as unused keys.
+                let sections: Vec<String> = profiles
+                    .iter()
+                    .filter(|(_, v)| v.is_object())
+                    .map(|(k, _)| k.to_string())
+                    .collect();
+                for k in sections {
+                    if let Some(v) = profiles.remove(&k) {
+                        out.push((Some(k), v));
+                    }
+                }
+                if profiles.is_empty() {
+                    o.remove(PROFILE_KEY);
+                }
+            }
+        }
+        out.insert(0, (None, v));
+        out
+    }
+
+    fn locate(content: &str) -> Vec<(String, usize, usize)> {
+        let mut locator = Locator {
+            content,
+            pos: 0,
+            out: vec![],
+        };
+        locator.value("");
+        locator.out
+    }
+}
+
+/// Scan json text for positions of keys and array items, stops at the first malformed token.
+struct Locator<'a> {
+    content: &'a str,
+    pos: usize,
+    out: Vec<(String, usize, usize)>,
+}
+
+impl Locator<'_> {
+    fn skip_ws(&mut self) {
+        let rest = &self.content[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.content.as_bytes().get(self.pos).copied()
+    }
+
+    fn push(&mut self, key: String, at: usize) {
+        let (line, column) = line_column(self.content, at);
+        self.out.push((key, line, column));
+    }
+
+    /// Scan string and return its raw content.
+    fn string(&mut self) -> Option<&str> {
+        let bytes = self.content.as_bytes();
+        let start = self.pos + 1;
+        let mut i = start;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => {
+                    self.pos = i + 1;
+                    return Some(&self.content[start..i]);
+                }
+                _ => i += 1,
+            }
+        }
+        None
+    }
+
+    fn value(&mut self, key: &str) -> Option<()> {
+        self.skip_ws();
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_ws();
+                    match self.peek()? {
+                        b'}' => break,
+                        b',' => self.pos += 1,
+                        b'"' => {
+                            let at = self.pos;
+                            let sub = format!("{}.{}", key, self.string()?);
+                            self.push(sub.clone(), at);
+                            self.skip_ws();
+                            if self.peek()? != b':' {
+                                return None;
+                            }
+                            self.pos += 1;
+                            self.value(&sub)?;
+                        }
+                        _ => return None,
+                    }
+                }
+                self.pos += 1;
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut i = 0;
+                loop {
+                    self.skip_ws();
+                    match self.peek()? {
+                        b']' => break,
+                        b',' => self.pos += 1,
+                        _ => {
+                            let sub = format!("{}.{}", key, i);
+                            self.push(sub.clone(), self.pos);
+                            self.value(&sub)?;
+                            i += 1;
+                        }
+                    }
+                }
+                self.pos += 1;
+            }
+            b'"' => {
+                self.string()?;
+            }
+            _ => {
+                let rest = &self.content[self.pos..];
+                let len = rest
+                    .find(|c: char| c == ',' || c == '}' || c == ']' || c.is_whitespace())
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return None;
+                }
+                self.pos += len;
+            }
+        }
+        Some(())
+    }
 }
 
 #[cfg_attr(coverage_nightly, coverage(off))]
 #[cfg(test)]
 mod test {
-    use crate::{inline_source, test::source_test_suit, ConfigError};
+    use super::*;
+    use crate::{
+        inline_source_config,
+        source::inline_source,
+        test::{null_test_suit, replace_test_suit, source_test_suit},
+    };
 
     #[test]
     #[allow(unused_qualifications)]
     fn inline_test() -> Result<(), ConfigError> {
         source_test_suit(inline_source!("../../app.json")?)
     }
+
+    #[test]
+    fn number_test() {
+        macro_rules! should_eq {
+            ($v:literal => $p:pat) => {
+                let v = convert_number(json::parse($v).unwrap().as_number().unwrap());
+                assert!(matches!(v, $p), "{} => {:?}", $v, v);
+            };
+        }
+        should_eq!("42" => ConfigValue::Int(42));
+        should_eq!("-42" => ConfigValue::Int(-42));
+        should_eq!("1e3" => ConfigValue::Int(1000));
+        should_eq!("18446744073709551615" => ConfigValue::BigInt(18446744073709551615));
+        should_eq!("3.0" => ConfigValue::Float(3.0));
+        should_eq!("-0.5" => ConfigValue::Float(-0.5));
+        should_eq!("1e-300" => ConfigValue::Float(1e-300));
+        should_eq!("0.12345678901234567" => ConfigValue::Decimal(_));
+        should_eq!("1e300" => ConfigValue::Decimal(_));
+    }
+
+    #[test]
+    fn null_test() -> Result<(), ConfigError> {
+        null_test_suit(inline_source_config::<Json>("json".into(), r#"{"a": null, "b": null, "d": "x"}"#)?)
+    }
+
+    #[test]
+    fn replace_test() -> Result<(), ConfigError> {
+        replace_test_suit(inline_source_config::<Json>("json".into(), r#"{"a": {"_replace": true, "x": "x"}, "b": {"x": "x"}}"#)?)
+    }
+
+    #[test]
+    fn split_profiles_test() -> Result<(), ConfigError> {
+        let v = Json::parse_source(
+            r#"{"a": 1, "profile": {"x": 1}, "_profiles": {"prod": {"a": 2}, "x": 1}}"#,
+        )?;
+        let v = Json::split_profiles(v);
+        assert_eq!(
+            vec![
+                (None, Json::parse_source(r#"{"a": 1, "profile": {"x": 1}, "_profiles": {"x": 1}}"#)?),
+                (Some("prod".to_string()), Json::parse_source(r#"{"a": 2}"#)?),
+            ],
+            v
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let e = Json::parse_source("{\n  \"a\": 1,\n  x\n}").err().unwrap();
+        assert!(matches!(e, ConfigError::ConfigCause(_)));
+        let c = e.context().unwrap();
+        assert_eq!((Some(3), Some(3)), (c.line, c.column));
+    }
+
+    #[test]
+    fn locate_test() {
+        let locations = Json::locate("{\n  \"a\": 1,\n  \"b\": {\"c\": [\"x\", {\"d\": \"}\\\"\"}]}\n}");
+        assert_eq!(
+            vec![
+                (".a".to_string(), 2, 3),
+                (".b".to_string(), 3, 3),
+                (".b.c".to_string(), 3, 9),
+                (".b.c.0".to_string(), 3, 15),
+                (".b.c.1".to_string(), 3, 20),
+                (".b.c.1.d".to_string(), 3, 21),
+            ],
+            locations
+        );
+        assert_eq!(vec![(".a".to_string(), 1, 2)], Json::locate("{\"a\": tru"));
+    }
 }
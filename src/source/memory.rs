@@ -2,11 +2,17 @@
 use std::{
     borrow::Borrow,
     collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     vec,
 };
 
 use crate::{
-    ConfigError, ConfigKey, ConfigValue, PartialKeyCollector,
+    ArrayMerge, CoercionPolicy, ConfigError, ConfigKey, ConfigValue, Definition, Location,
+    PartialKeyCollector, PlaceholderResolver, ToConfig,
     key::{PartialKey, PartialKeyIter},
     source::{ConfigSource, ConfigSourceAdaptor},
     value_ref::Refresher,
@@ -19,6 +25,13 @@ pub struct HashSource {
     pub(crate) value: HashMap<String, HashValue>,
     name: String,
     pub(crate) refs: Refresher,
+    pub(crate) policy: CoercionPolicy,
+    pub(crate) merge: Vec<(String, ArrayMerge)>,
+    layers: Vec<String>,
+    /// Placeholder resolvers by scheme.
+    pub(crate) resolvers: Vec<(String, Arc<dyn PlaceholderResolver>)>,
+    /// Evaluate expressions in placeholders.
+    pub(crate) expressions: bool,
 }
 
 impl ConfigSource for HashSource {
@@ -26,29 +39,74 @@ impl ConfigSource for HashSource {
         &self.name
     }
     fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        let mut nulls = vec![];
         for (k, v) in &self.value {
-            if let Some(v) = &v.value {
-                builder.set(k, v.clone_static());
+            for (value, o) in v.values() {
+                match value {
+                    ConfigValue::Null => nulls.push((k, &o.location)),
+                    value => builder.set_at(k, value.clone_static(), o.location.clone()),
+                }
             }
         }
+        // Values of the same source are kept.
+        for (k, location) in nulls {
+            builder.set_at(k, ConfigValue::Null, location.clone());
+        }
         Ok(())
     }
 }
 
+/// Directive key, `map._replace = true` makes the map replace maps of lower priority sources.
+const REPLACE_KEY: &str = "_replace";
+/// Directive key, `key._null = true` sets key to [`ConfigValue::Null`], for formats without null, such as toml.
+const NULL_KEY: &str = "_null";
+/// Max length of subtree reference chains.
+const MAX_REFERENCES: usize = 64;
+
+/// Parse subtree reference `${@target}`.
+fn parse_reference(value: &str) -> Option<&str> {
+    value
+        .trim()
+        .strip_prefix("${@")?
+        .strip_suffix('}')
+        .filter(|v| !v.is_empty() && !v.contains(['$', '{', '}']))
+}
+
 /// Hash Value.
 #[derive(Debug)]
 pub(crate) struct HashValue {
     sub_str: HashSet<String>,
     sub_int: Option<usize>,
+    /// Layer which owns the array, and index offset of current layer.
+    array: Option<(usize, usize)>,
+    /// Layer which replaces the whole map.
+    replace: Option<usize>,
     value: Option<ConfigValue<'static>>,
+    /// Sources defining the value, the first one supplies `value`.
+    origins: Vec<Origin>,
+    /// Looked up by contexts, kept when values are replaced.
+    used: AtomicBool,
+}
+
+/// Source defining a value.
+#[derive(Debug)]
+pub(crate) struct Origin {
+    layer: usize,
+    /// Shadowed value, `None` if it is the value in use.
+    value: Option<ConfigValue<'static>>,
+    location: Option<Location>,
 }
 
 /// Config source builder.
 #[derive(Debug)]
 pub struct ConfigSourceBuilder<'a> {
-    key: Vec<String>,
+    /// Pushed keys, and whether they are hidden by an explicit null or a replaced map.
+    key: Vec<(String, bool)>,
     map: &'a mut HashMap<String, HashValue>,
     count: usize,
+    layer: usize,
+    merge: &'a [(String, ArrayMerge)],
+    file: Option<PathBuf>,
 }
 
 impl HashValue {
@@ -57,15 +115,47 @@ impl HashValue {
         Self {
             sub_str: HashSet::new(),
             sub_int: None,
+            array: None,
+            replace: None,
             value: None,
+            origins: vec![],
+            used: AtomicBool::new(false),
         }
     }
 
     #[inline]
-    fn push_val<V: Into<ConfigValue<'static>>>(&mut self, val: V) {
+    fn is_null(&self) -> bool {
+        matches!(self.value, Some(ConfigValue::Null))
+    }
+
+    #[inline]
+    fn is_replaced(&self, layer: usize) -> bool {
+        self.replace.is_some_and(|l| l != layer)
+    }
+
+    #[inline]
+    fn push_val_at<V: Into<ConfigValue<'static>>>(
+        &mut self,
+        val: V,
+        layer: usize,
+        location: Option<Location>,
+    ) {
+        let mut value = Some(val.into());
         if self.value.is_none() {
-            self.value = Some(val.into());
+            self.value = value.take();
         }
+        self.origins.push(Origin {
+            layer,
+            value,
+            location,
+        });
+    }
+
+    /// All values with their origins, from highest priority to lowest.
+    fn values(&self) -> impl Iterator<Item = (&ConfigValue<'static>, &Origin)> {
+        self.origins
+            .iter()
+            .filter_map(move |o| o.value.as_ref().or(self.value.as_ref()).map(|v| (v, o)))
     }
 
     #[inline]
@@ -90,6 +180,11 @@ impl HashSource {
             value: HashMap::new(),
             name: name.into(),
             refs: Refresher::new(),
+            policy: CoercionPolicy::Default,
+            merge: vec![],
+            layers: vec![],
+            resolvers: vec![],
+            expressions: false,
         }
     }
 
@@ -99,32 +194,243 @@ impl HashSource {
             key: vec![],
             map: &mut self.value,
             count: 0,
+            layer: 0,
+            merge: &self.merge,
+            file: None,
+        }
+    }
+
+    /// Replace values by values of other source, used keys are kept.
+    pub(crate) fn replace_values(&mut self, mut other: HashSource) {
+        for (k, v) in &self.value {
+            if v.used.load(Ordering::Relaxed) {
+                if let Some(o) = other.value.get_mut(k) {
+                    *o.used.get_mut() = true;
+                }
+            }
+        }
+        self.value = other.value;
+        self.layers = other.layers;
+    }
+
+    /// Builder of next source layer named `name`, arrays are merged across layers by [`ArrayMerge`].
+    #[inline]
+    pub(crate) fn next_layer(&mut self, name: &str) -> ConfigSourceBuilder<'_> {
+        self.layers.push(name.to_string());
+        ConfigSourceBuilder {
+            key: vec![],
+            map: &mut self.value,
+            count: 0,
+            layer: self.layers.len(),
+            merge: &self.merge,
+            file: None,
+        }
+    }
+
+    fn layer_name(&self, layer: usize) -> &str {
+        match layer {
+            0 => &self.name,
+            l => &self.layers[l - 1],
+        }
+    }
+
+    /// Sources defining the key, from highest priority to lowest.
+    pub(crate) fn definitions(&self, key: &ConfigKey<'_>) -> Vec<Definition> {
+        self.value
+            .get(key.as_str())
+            .map(|v| {
+                v.values()
+                    .map(|(value, o)| Definition {
+                        source: self.layer_name(o.layer).to_string(),
+                        value: value.clone_static(),
+                        location: o.location.clone(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn resolver(&self, scheme: &str) -> Option<&dyn PlaceholderResolver> {
+        self.resolvers
+            .iter()
+            .find(|(s, _)| s == scheme)
+            .map(|(_, r)| r.as_ref())
+    }
+
+    /// Name of source supplying value of `key`, and location of value.
+    pub(crate) fn origin_of(&self, key: &str) -> Option<(&str, Option<&Location>)> {
+        self.value
+            .get(key)
+            .and_then(|v| v.origins.first())
+            .map(|o| (self.layer_name(o.layer), o.location.as_ref()))
+    }
+
+    /// Mark `key` as used.
+    pub(crate) fn mark_used(&self, key: &str) {
+        if let Some(v) = self.value.get(key) {
+            if !v.used.load(Ordering::Relaxed) {
+                v.used.store(true, Ordering::Relaxed);
+            }
         }
     }
 
+    pub(crate) fn is_used(&self, key: &str) -> bool {
+        self.value
+            .get(key)
+            .is_some_and(|v| v.used.load(Ordering::Relaxed))
+    }
+
+    /// Keys with values, explicit nulls are excluded, sorted by key.
+    pub(crate) fn entries(&self) -> Vec<(&str, &ConfigValue<'static>)> {
+        let mut v: Vec<_> = self
+            .value
+            .iter()
+            .filter_map(|(k, v)| match &v.value {
+                None | Some(ConfigValue::Null) => None,
+                Some(value) => Some((k.as_str(), value)),
+            })
+            .collect();
+        v.sort_by(|a, b| a.0.cmp(b.0));
+        v
+    }
+
+    #[cfg(test)]
     pub(crate) fn get_value(&self, key: &ConfigKey<'_>) -> Option<ConfigValue<'_>> {
+        self.value_of(key.as_str())
+    }
+
+    /// Get value of `key`, reading through subtree references `${@target}` of the key or its parents.
+    ///
+    /// Values defined under `key` itself take precedence, explicit null is not read through.
+    pub(crate) fn lookup(
+        &self,
+        key: &ConfigKey<'_>,
+        track: bool,
+    ) -> Result<Option<ConfigValue<'_>>, ConfigError> {
         let key = key.as_str();
+        if !self.is_reference(key) {
+            return Ok(self.value_of(key));
+        }
+        for alias in self.aliases(key)? {
+            if track {
+                self.mark_used(&alias);
+            }
+            if !self.is_reference(&alias) {
+                return Ok(self.value_of(&alias));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Target of subtree reference, i.e. value of `key` is `${@target}`.
+    fn reference(&self, key: &str) -> Option<&str> {
+        match self.value.get(key)?.value.as_ref()? {
+            ConfigValue::StrRef(v) => parse_reference(v),
+            ConfigValue::Str(v) => parse_reference(v),
+            _ => None,
+        }
+    }
+
+    /// Check if `key` has no value of its own, or its value is a subtree reference.
+    fn is_reference(&self, key: &str) -> bool {
+        match self.value.get(key).and_then(|v| v.value.as_ref()) {
+            None => true,
+            Some(ConfigValue::Null) => false,
+            Some(_) => self.reference(key).is_some(),
+        }
+    }
+
+    /// Key which `key` reads through by the subtree reference of its nearest parent, or itself.
+    fn referenced(&self, key: &str) -> Option<String> {
+        let bytes = key.as_bytes();
+        (1..=key.len())
+            .rev()
+            .filter(|&i| i == key.len() || bytes[i] == b'.' || bytes[i] == b'[')
+            .find_map(|i| {
+                self.reference(&key[..i])
+                    .map(|target| format!("{}{}", target, &key[i..]))
+            })
+    }
+
+    /// Keys which `key` reads through by subtree references, nearest first.
+    pub(crate) fn aliases(&self, key: &str) -> Result<Vec<String>, ConfigError> {
+        let mut aliases: Vec<String> = vec![];
+        while let Some(next) = self.referenced(aliases.last().map_or(key, |v| v.as_str())) {
+            if next == key || aliases.contains(&next) || aliases.len() >= MAX_REFERENCES {
+                aliases.push(next);
+                let chain: Vec<&str> = std::iter::once(key)
+                    .chain(aliases.iter().map(|v| v.as_str()))
+                    .collect();
+                return Err(ConfigError::ConfigRecursiveError(chain.join(" -> ")));
+            }
+            aliases.push(next);
+        }
+        Ok(aliases)
+    }
+
+    fn value_of(&self, key: &str) -> Option<ConfigValue<'_>> {
         self.value
             .get(key)
             .and_then(|f| f.value.as_ref())
-            .map(|v| match v {
-                ConfigValue::StrRef(v) => ConfigValue::StrRef(v),
-                ConfigValue::Str(v) => ConfigValue::StrRef(v),
-                ConfigValue::Int(v) => ConfigValue::Int(*v),
-                ConfigValue::Float(v) => ConfigValue::Float(*v),
-                ConfigValue::Bool(v) => ConfigValue::Bool(*v),
-                #[cfg(feature = "rand")]
-                ConfigValue::Rand(v) => ConfigValue::Rand(*v),
+            .and_then(|v| {
+                Some(match v {
+                    ConfigValue::StrRef(v) => ConfigValue::StrRef(v),
+                    ConfigValue::Str(v) => ConfigValue::StrRef(v),
+                    ConfigValue::Int(v) => ConfigValue::Int(*v),
+                    ConfigValue::Float(v) => ConfigValue::Float(*v),
+                    ConfigValue::BigInt(v) => ConfigValue::BigInt(*v),
+                    ConfigValue::Decimal(v) => ConfigValue::Decimal(v.clone()),
+                    ConfigValue::Bool(v) => ConfigValue::Bool(*v),
+                    ConfigValue::Null => return None,
+                    #[cfg(feature = "rand")]
+                    ConfigValue::Rand(v) => ConfigValue::Rand(*v),
+                })
             })
     }
 
+    #[cfg(any(test, feature = "serde"))]
+    pub(crate) fn contains_key(&self, key: &ConfigKey<'_>) -> bool {
+        let contains = |key: &str| {
+            self.value
+                .get(key)
+                .is_some_and(|v| !v.is_null() || !v.sub_str.is_empty() || v.sub_int.is_some())
+        };
+        let key = key.as_str();
+        contains(key)
+            || (!self.is_removed(key)
+                && self
+                    .aliases(key)
+                    .is_ok_and(|aliases| aliases.iter().any(|k| contains(k))))
+    }
+
+    /// Collect sub keys of `prefix`, including sub keys read through subtree references.
     pub(crate) fn collect_keys<'a>(
         &'a self,
         prefix: &ConfigKey<'_>,
         sub: &mut PartialKeyCollector<'a>,
     ) {
-        if let Some(v) = self.value.get(prefix.as_str()) {
+        self.collect_keys_of(prefix.as_str(), sub);
+        if !self.is_removed(prefix.as_str()) {
+            // Recursive references are reported when reading values.
+            for alias in self.aliases(prefix.as_str()).unwrap_or_default() {
+                self.collect_keys_of(&alias, sub);
+            }
+        }
+    }
+
+    fn collect_keys_of<'a>(&'a self, prefix: &str, sub: &mut PartialKeyCollector<'a>) {
+        if let Some(v) = self.value.get(prefix) {
             for k in v.sub_str.iter() {
+                // Nulled keys without sub keys are absent.
+                let mut key = prefix.to_string();
+                PartialKey::Str(k).update_string(&mut key);
+                if self
+                    .value
+                    .get(&key)
+                    .is_some_and(|v| v.is_null() && v.sub_str.is_empty() && v.sub_int.is_none())
+                {
+                    continue;
+                }
                 sub.str_key.insert(k.as_str());
             }
             if let Some(i) = v.sub_int {
@@ -132,6 +438,11 @@ impl HashSource {
             }
         }
     }
+
+    fn is_removed(&self, key: &str) -> bool {
+        self.value.get(key).is_some_and(|v| v.is_null())
+    }
+
     pub(crate) fn set<K: Borrow<str>, V: Into<ConfigValue<'static>>>(mut self, k: K, v: V) -> Self {
         let mut c = self.prefixed();
         c.set(k.borrow(), v);
@@ -152,6 +463,57 @@ impl ConfigSourceBuilder<'_> {
         self.pop();
     }
 
+    #[allow(single_use_lifetimes)]
+    pub(crate) fn set_at<'b, K: Into<PartialKeyIter<'b>>, V: Into<ConfigValue<'static>>>(
+        &mut self,
+        k: K,
+        v: V,
+        location: Option<Location>,
+    ) {
+        self.push(k);
+        self.insert_at(v, location);
+        self.pop();
+    }
+
+    /// Set file of values inserted afterwards.
+    pub(crate) fn set_file(&mut self, file: Option<PathBuf>) {
+        self.file = file;
+    }
+
+    /// Set line and column of value `key` in current file.
+    pub(crate) fn set_location(&mut self, key: &str, line: usize, column: usize) {
+        let mut curr = self.curr();
+        for k in PartialKeyIter::from(key) {
+            k.update_string(&mut curr);
+        }
+        let (Some(v), Some(file)) = (self.map.get_mut(&curr), &self.file) else {
+            return;
+        };
+        if let Some(l) = v
+            .origins
+            .iter_mut()
+            .filter(|o| o.layer == self.layer)
+            .filter_map(|o| o.location.as_mut())
+            .find(|l| &l.path == file && l.line.is_none())
+        {
+            l.line = Some(line);
+            l.column = Some(column);
+        }
+    }
+
+    /// Set value of [`ToConfig`] instance.
+    #[allow(single_use_lifetimes)]
+    pub fn set_config<'b, K: Into<PartialKeyIter<'b>>, V: ToConfig + ?Sized>(
+        &mut self,
+        k: K,
+        v: &V,
+    ) -> Result<(), ConfigError> {
+        self.push(k);
+        let x = v.to_config(self);
+        self.pop();
+        x
+    }
+
     /// Insert map into source.
     pub fn insert_map<I: IntoIterator<Item = (K, V)>, K: Borrow<str>, V: ConfigSourceAdaptor>(
         &mut self,
@@ -182,16 +544,56 @@ impl ConfigSourceBuilder<'_> {
 
     #[inline]
     fn push<'b, K: Into<PartialKeyIter<'b>>>(&mut self, key: K) {
-        let mut curr = self.curr();
-        let mut vs = vec![];
+        let (mut curr, mut blocked) = self.key.last().cloned().unwrap_or_default();
         let iter: PartialKeyIter<'b> = key.into();
-        for k in iter {
-            let v = self.map.entry(curr.clone()).or_insert_with(HashValue::new);
-            v.push_key(&k);
+        for mut k in iter {
+            if !blocked {
+                let merge = match k {
+                    PartialKey::Int(_) => self.array_merge(&curr),
+                    _ => ArrayMerge::MergeByIndex,
+                };
+                let v = self.map.entry(curr.clone()).or_insert_with(HashValue::new);
+                // Keys under an explicit null or a replaced map are removed.
+                blocked = v.is_null() || v.is_replaced(self.layer);
+                if let PartialKey::Int(i) = &mut k {
+                    match v.array {
+                        None => v.array = Some((self.layer, 0)),
+                        Some((layer, _)) if layer == self.layer => {}
+                        Some(_) => match merge {
+                            ArrayMerge::Replace => blocked = true,
+                            ArrayMerge::Append => {
+                                v.array = Some((self.layer, v.sub_int.map_or(0, |n| n + 1)))
+                            }
+                            ArrayMerge::MergeByIndex => {}
+                        },
+                    }
+                    if merge == ArrayMerge::Append {
+                        *i += v.array.map_or(0, |(_, base)| base);
+                    }
+                }
+                if !blocked
+                    && (self.layer == 0
+                        || (k != PartialKey::Str(REPLACE_KEY) && k != PartialKey::Str(NULL_KEY)))
+                {
+                    v.push_key(&k);
+                }
+            }
             k.update_string(&mut curr);
-            vs.push(k);
         }
-        self.key.push(curr);
+        self.key.push((curr, blocked));
+    }
+
+    fn array_merge(&self, key: &str) -> ArrayMerge {
+        self.merge
+            .iter()
+            .filter(|(p, _)| {
+                key.strip_prefix(p.as_str()).is_some_and(|r| {
+                    p.is_empty() || r.is_empty() || r.starts_with('.') || r.starts_with('[')
+                })
+            })
+            .max_by_key(|(p, _)| p.len())
+            .map(|(_, m)| *m)
+            .unwrap_or_default()
     }
 
     #[inline]
@@ -200,10 +602,10 @@ impl ConfigSourceBuilder<'_> {
     }
 
     #[inline]
-    fn curr(&self) -> String {
+    pub(crate) fn curr(&self) -> String {
         self.key
             .last()
-            .map(|f| f.as_str())
+            .map(|f| f.0.as_str())
             .unwrap_or("")
             .to_string()
     }
@@ -211,11 +613,56 @@ impl ConfigSourceBuilder<'_> {
     /// Insert value into source.
     #[inline]
     pub fn insert<V: Into<ConfigValue<'static>>>(&mut self, value: V) {
-        self.count += 1;
-        self.map
-            .entry(self.curr())
-            .or_insert_with(HashValue::new)
-            .push_val(value);
+        let location = self.file.clone().map(Location::new);
+        self.insert_at(value, location);
+    }
+
+    fn insert_at<V: Into<ConfigValue<'static>>>(&mut self, value: V, location: Option<Location>) {
+        if self.key.last().is_some_and(|f| f.1) {
+            return;
+        }
+        let curr = self.curr();
+        if self.layer > 0 {
+            for directive in [REPLACE_KEY, NULL_KEY] {
+                let parent = match curr.strip_suffix(directive) {
+                    Some("") => Some(""),
+                    Some(p) => p.strip_suffix('.'),
+                    None => None,
+                };
+                if let Some(parent) = parent {
+                    let enabled = match value.into() {
+                        ConfigValue::Bool(v) => v,
+                        ConfigValue::StrRef(v) => v == "true",
+                        ConfigValue::Str(v) => v == "true",
+                        _ => false,
+                    };
+                    if enabled && directive == REPLACE_KEY {
+                        let v = self
+                            .map
+                            .entry(parent.to_string())
+                            .or_insert_with(HashValue::new);
+                        v.replace.get_or_insert(self.layer);
+                    } else if enabled {
+                        self.push_value(parent.to_string(), ConfigValue::Null, location);
+                    }
+                    return;
+                }
+            }
+        }
+        self.push_value(curr, value, location);
+    }
+
+    fn push_value<V: Into<ConfigValue<'static>>>(
+        &mut self,
+        key: String,
+        value: V,
+        location: Option<Location>,
+    ) {
+        let v = self.map.entry(key).or_insert_with(HashValue::new);
+        if !v.is_replaced(self.layer) {
+            self.count += 1;
+            v.push_val_at(value, self.layer, location);
+        }
     }
 
     pub(crate) fn count(&self) -> usize {
@@ -284,8 +731,8 @@ mod tests {
         hv.push_key(&PartialKey::Str("abc"));
         hv.push_key(&PartialKey::Int(2));
         hv.push_key(&PartialKey::Int(1));
-        hv.push_val("val");
-        hv.push_val("should_not_overwrite");
+        hv.push_val_at("val", 0, None);
+        hv.push_val_at("should_not_overwrite", 0, None);
         assert!(hv.sub_str.contains("abc"));
         assert_eq!(hv.sub_int, Some(2));
         match hv.value {
@@ -407,4 +854,110 @@ mod tests {
         key.push("notfound");
         assert!(hs.get_value(&key).is_none());
     }
+
+    #[test]
+    fn null_removes_lower_keys_test() {
+        use crate::FromConfig;
+        #[derive(Debug, FromConfig)]
+        #[config(crate = "crate")]
+        struct App {
+            #[config(default = "8080")]
+            port: u16,
+            name: Option<String>,
+        }
+        let config = crate::Configuration::new()
+            .register_kv("high")
+            .set("app.port", ConfigValue::Null)
+            .set("app.name", ConfigValue::Null)
+            .set("app.db", ConfigValue::Null)
+            .set("app.host", "high")
+            .finish()
+            .unwrap()
+            .register_kv("low")
+            .set("app.port", 80)
+            .set("app.name", "low")
+            .set("app.db.url", "low")
+            .set("app.db[0]", "low")
+            .set("app.host", "low")
+            .finish()
+            .unwrap();
+        let app: App = config.get("app").unwrap();
+        assert_eq!(8080, app.port);
+        assert_eq!(None, app.name);
+        assert_eq!(None, config.get::<Option<String>>("app.db.url").unwrap());
+        assert_eq!(None, config.get::<Option<String>>("app.db").unwrap());
+        assert_eq!(
+            Vec::<String>::new(),
+            config.get::<Vec<String>>("app.db").unwrap()
+        );
+        assert_eq!("high", config.get::<String>("app.host").unwrap());
+        let app = config.get::<HashMap<String, String>>("app").unwrap();
+        assert_eq!(1, app.len());
+        assert_eq!("high", app["host"]);
+    }
+
+    #[test]
+    fn null_keeps_higher_keys_test() {
+        let config = crate::Configuration::new()
+            .register_kv("high")
+            .set("app.db.url", "high")
+            .finish()
+            .unwrap()
+            .register_kv("low")
+            .set("app.db", ConfigValue::Null)
+            .set("app.db.user", "low")
+            .finish()
+            .unwrap();
+        assert_eq!("high", config.get::<String>("app.db.url").unwrap());
+        assert_eq!(None, config.get::<Option<String>>("app.db.user").unwrap());
+
+        let mut hs = HashSource::new("test");
+        {
+            let mut builder = hs.prefixed();
+            builder.set("a.b", 1);
+            builder.set("a", ConfigValue::Null);
+        }
+        let mut cache = crate::key::CacheString::new();
+        let mut key = cache.new_key();
+        key.push("a");
+        assert!(hs.get_value(&key).is_none());
+        assert!(hs.contains_key(&key));
+        let mut target = HashSource::new("target");
+        hs.load(&mut target.prefixed()).unwrap();
+        key.push("b");
+        assert!(matches!(target.get_value(&key), Some(ConfigValue::Int(1))));
+    }
+
+    #[test]
+    fn replace_map_test() {
+        use std::collections::HashMap;
+        let config = crate::Configuration::new()
+            .register_kv("high")
+            .set("routes.a", "high")
+            .finish()
+            .unwrap()
+            .register_kv("profile")
+            .set("routes._replace", true)
+            .set("routes.b", "profile")
+            .set("other._replace", false)
+            .set("other.x", "profile")
+            .finish()
+            .unwrap()
+            .register_kv("base")
+            .set("routes", "base")
+            .set("routes.c", "base")
+            .set("routes.d.e", "base")
+            .set("other.y", "base")
+            .finish()
+            .unwrap();
+        let routes = config.get::<HashMap<String, String>>("routes").unwrap();
+        assert_eq!(2, routes.len());
+        assert_eq!("high", routes["a"]);
+        assert_eq!("profile", routes["b"]);
+        assert_eq!(None, config.get::<Option<String>>("routes").unwrap());
+        assert_eq!(None, config.get::<Option<String>>("routes.d.e").unwrap());
+        let other = config.get::<HashMap<String, String>>("other").unwrap();
+        assert_eq!(2, other.len());
+        assert_eq!("base", other["y"]);
+    }
 }
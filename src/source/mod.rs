@@ -2,8 +2,9 @@
 use crate::*;
 
 #[allow(unused_imports)]
-use self::file::FileLoader;
-use std::path::PathBuf;
+use self::file::{FileLoader, Section, list_files};
+use crate::{cache::CacheConfigSource, configuration::is_under};
+use std::path::{Path, PathBuf};
 
 /// Config key module.
 pub mod key {
@@ -11,6 +12,9 @@ pub mod key {
 }
 pub use super::configuration::ManualSource;
 pub use memory::ConfigSourceBuilder;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub use serialize::SerializeSource;
 
 pub(crate) mod cargo;
 pub(crate) mod environment;
@@ -20,6 +24,8 @@ pub(crate) mod memory;
 #[cfg(feature = "rand")]
 #[cfg_attr(docsrs, doc(cfg(feature = "rand")))]
 pub(crate) mod random;
+#[cfg(feature = "serde")]
+pub(crate) mod serialize;
 
 #[allow(dead_code)]
 #[derive(Debug, FromConfig)]
@@ -49,13 +55,12 @@ pub(crate) struct SourceOption {
     )+
 }
 
-#[inline]
-#[allow(unreachable_code, unused_variables, unused_mut)]
-pub(crate) fn register_by_ext(
-    mut config: Configuration,
+/// File source, file extension is used to choose the parser.
+#[allow(unreachable_code, unused_variables)]
+pub(crate) fn loader_by_ext(
     path: PathBuf,
     required: bool,
-) -> Result<Configuration, ConfigError> {
+) -> Result<Box<dyn ConfigSource + Send + 'static>, ConfigError> {
     let ext = path
         .extension()
         .and_then(|x| x.to_str())
@@ -63,16 +68,34 @@ pub(crate) fn register_by_ext(
         match ext {
             $(
                 #[cfg(feature = $name)]
-                $($k)|* => {
-                    config = config.register_source(<FileLoader<$x>>::new(
-                        path.clone(),
-                        required,
-                        true,
-                    ))?;
-                }
+                $($k)|* => Ok(Box::new(<FileLoader<$x>>::new(path, required, true))),
             )+
-            _ => return Err(ConfigError::ConfigFileNotSupported(path)),
+            _ => Err(ConfigError::ConfigFileNotSupported(path)),
         }
+}
+
+/// Check if file extension is supported by enabled features.
+pub(crate) fn is_supported(path: &Path) -> bool {
+    match path.extension().and_then(|x| x.to_str()) {
+        $(
+            #[cfg(feature = $name)]
+            Some($($k)|*) => true,
+        )+
+        _ => false,
+    }
+}
+
+#[inline]
+pub(crate) fn register_by_ext(
+    mut config: Configuration,
+    path: PathBuf,
+    required: bool,
+) -> Result<Configuration, ConfigError> {
+    let mut chain: Vec<PathBuf> = vec![canonical(path.clone())];
+    let loader = loader_by_ext(path.clone(), required)?;
+    let imports = load_imports(loader, &path, &mut chain)?;
+    let pos = config.next_position(0);
+    config.insert_imports(imports, 0, pos)?;
     Ok(config)
 }
 
@@ -82,12 +105,25 @@ pub(crate) fn register_files(
     option: &SourceOption,
     path: PathBuf,
     has_ext: bool,
+    priority: i32,
+    section: &Section,
 ) -> Result<Configuration, ConfigError> {
     $(
     #[cfg(feature = $name)]
     if option.$nm.enabled {
-        config =
-            config.register_source(<FileLoader<$x>>::new(path.clone(), false, has_ext))?;
+        let mut chain: Vec<PathBuf> = if has_ext {
+            vec![canonical(path.clone())]
+        } else {
+            <$x>::file_extensions()
+                .into_iter()
+                .map(|ext| canonical(path.with_extension(ext)))
+                .collect()
+        };
+        let loader =
+            <FileLoader<$x>>::new(path.clone(), false, has_ext).with_section(section.clone());
+        let imports = load_imports(loader, &path, &mut chain)?;
+        let pos = config.next_position(priority);
+        config.insert_imports(imports, priority, pos)?;
     }
     )+
     Ok(config)
@@ -157,6 +193,87 @@ macro_rules! inline_source_internal {
     };
 }
 
+/// Files imported by a config file, such as `app.imports = ["db.toml", "optional:local.toml", "conf.d/*.yaml"]`.
+#[derive(Debug, FromConfig)]
+#[config(prefix = "app", crate = "crate")]
+pub(crate) struct ImportOption {
+    /// Paths relative to the importing file.
+    pub(crate) imports: Vec<String>,
+    /// Imported files override the importing file, by default the importing file overrides them.
+    #[config(default = false)]
+    pub(crate) imports_override: bool,
+}
+
+/// Sources of a file and files imported by it from highest priority to lowest, and keys of imports.
+pub(crate) struct Imports {
+    pub(crate) loaders: Vec<Box<dyn ConfigSource + Send + 'static>>,
+    pub(crate) keys: Vec<String>,
+}
+
+/// Prefix of imports which may not exist.
+const OPTIONAL_IMPORT: &str = "optional:";
+
+fn canonical(path: PathBuf) -> PathBuf {
+    std::fs::canonicalize(&path).unwrap_or(path)
+}
+
+/// Load source of `importer` once, and files imported by it, just below it,
+/// or above it if `app.imports_override` is true.
+///
+/// Later imports override earlier ones, `chain` holds the importing files to detect cycles.
+fn load_imports<L: ConfigSource + 'static>(
+    loader: L,
+    importer: &Path,
+    chain: &mut Vec<PathBuf>,
+) -> Result<Imports, ConfigError> {
+    let mut config = Configuration::new();
+    loader.load(&mut config.source.next_layer(loader.name()))?;
+    let option: ImportOption = config.get_predefined()?;
+    let mut keys: Vec<String> = config
+        .source
+        .entries()
+        .into_iter()
+        .map(|(k, _)| k)
+        .filter(|k| is_under(k, "app.imports") || is_under(k, "app.imports_override"))
+        .map(|k| k.to_string())
+        .collect();
+    // Values loaded are reused, so that file is read once.
+    let mut loaders: Vec<Box<dyn ConfigSource + Send + 'static>> = vec![Box::new(
+        CacheConfigSource::with_values(loader, config.source),
+    )];
+    let dir = importer.parent().unwrap_or_else(|| Path::new(""));
+    for import in option.imports {
+        let (required, import) = match import.strip_prefix(OPTIONAL_IMPORT) {
+            Some(v) => (false, v),
+            None => (true, import.as_str()),
+        };
+        // Files matched by glob may be removed later, they are not required.
+        let (required, files) = if import.contains('*') {
+            let mut files = list_files(dir, import)?;
+            files.retain(|f| is_supported(f));
+            (false, files)
+        } else {
+            (required, vec![dir.join(import)])
+        };
+        for file in files {
+            let path = canonical(file.clone());
+            if chain.contains(&path) {
+                let mut files: Vec<String> =
+                    chain.iter().map(|p| p.display().to_string()).collect();
+                files.push(path.display().to_string());
+                return Err(ConfigError::ConfigRecursiveError(files.join(" -> ")));
+            }
+            chain.push(path);
+            let imports = load_imports(loader_by_ext(file.clone(), required)?, &file, chain)?;
+            chain.pop();
+            let at = if option.imports_override { 0 } else { 1 };
+            loaders.splice(at..at, imports.loaders);
+            keys.extend(imports.keys);
+        }
+    }
+    Ok(Imports { loaders, keys })
+}
+
 /// Config source adaptor is an intermediate representation of config source.
 /// It can convert to [`ConfigSource`]. We have toml, yaml and json values implement this trait.
 ///
@@ -171,6 +288,17 @@ pub trait ConfigSourceAdaptor {
     fn convert_source(self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError>;
 }
 
+/// Line and column of byte `offset` in `content`, both start from 1.
+#[cfg(any(feature = "toml", feature = "json"))]
+pub(crate) fn line_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
 /// Parse config source from string.
 pub trait ConfigSourceParser: Send {
     /// Config source adaptor.
@@ -181,6 +309,35 @@ pub trait ConfigSourceParser: Send {
 
     /// File extenstions.
     fn file_extensions() -> Vec<&'static str>;
+
+    /// Locate values in content, returns keys with their line and column, both start from 1.
+    ///
+    /// It is used by [`Configuration::explain`], default implementation locates nothing.
+    fn locate(_: &str) -> Vec<(String, usize, usize)> {
+        vec![]
+    }
+
+    /// Split source into documents with the profiles activating them, documents of `None` are always active.
+    ///
+    /// It is used by [`PredefinedConfigurationBuilder::init`] to load profile sections, such as `[_profiles.prod]`,
+    /// default implementation returns the whole source as one document.
+    fn split_profiles(adaptor: Self::Adaptor) -> Vec<(Option<String>, Self::Adaptor)> {
+        vec![(None, adaptor)]
+    }
+}
+
+/// Reserved key of profile sections, such as `[_profiles.prod]`.
+///
+/// It is not `profile`, so that existing `profile.*` keys are still loaded as they are,
+/// reserved keys start with `_`, like directives `_replace` and `_null`.
+pub(crate) const PROFILE_KEY: &str = "_profiles";
+/// Directive key, yaml document with `_profile: prod` is activated by profile `prod`.
+#[cfg(feature = "yaml")]
+pub(crate) const PROFILE_DIRECTIVE: &str = "_profile";
+
+/// Split profile names separated by comma, such as `prod,eu-west`.
+pub(crate) fn split_profile_names(v: &str) -> impl Iterator<Item = &str> {
+    v.split(',').map(str::trim).filter(|v| !v.is_empty())
 }
 
 /// Config source.
@@ -210,3 +367,21 @@ pub trait ConfigSource: Send {
         Ok(false)
     }
 }
+
+impl<L: ConfigSource + ?Sized> ConfigSource for Box<L> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        (**self).load(builder)
+    }
+
+    fn allow_refresh(&self) -> bool {
+        (**self).allow_refresh()
+    }
+
+    fn refreshable(&self) -> Result<bool, ConfigError> {
+        (**self).refreshable()
+    }
+}
@@ -0,0 +1,644 @@
+//! Serde serialize source.
+use std::fmt::Display;
+
+use serde::{
+    Serialize,
+    ser::{self, Impossible},
+};
+
+use crate::{ConfigError, ConfigValue};
+
+use super::{ConfigSource, ConfigSourceAdaptor, memory::ConfigSourceBuilder};
+
+impl ser::Error for ConfigError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ConfigError::ConfigParseError(String::new(), msg.to_string())
+    }
+}
+
+/// Config source built from any [`serde::Serialize`] value, must enable feature **serde**.
+///
+/// Struct fields and map entries become string keys, sequences become index keys,
+/// and scalars keep their types. `None` and unit values are skipped.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use cfg_rs::{*, source::SerializeSource};
+/// #[derive(serde::Serialize)]
+/// struct Defaults {
+///     port: u16,
+///     hosts: Vec<String>,
+/// }
+/// let config = Configuration::new()
+///     .register_kv("inline")
+///     .set("port", 9090)
+///     .finish()
+///     .unwrap()
+///     // Lowest priority, registered last.
+///     .register_source(SerializeSource::new(
+///         "defaults",
+///         Defaults { port: 8080, hosts: vec!["localhost".into()] },
+///     ))
+///     .unwrap();
+/// assert_eq!(9090, config.get::<u16>("port").unwrap());
+/// assert_eq!(vec!["localhost"], config.get::<Vec<String>>("hosts").unwrap());
+/// # }
+/// ```
+///
+/// Keys are relative to root, wrap the value in a map or struct to put it under a prefix.
+#[derive(Debug)]
+pub struct SerializeSource<T> {
+    name: String,
+    value: T,
+}
+
+impl<T: Serialize + Send> SerializeSource<T> {
+    /// Create source with name and value.
+    pub fn new<N: Into<String>>(name: N, value: T) -> Self {
+        Self {
+            name: format!("serialize:{}", name.into()),
+            value,
+        }
+    }
+}
+
+impl<T: Serialize + Send> ConfigSource for SerializeSource<T> {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn load(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.value
+            .serialize(ValueSerializer)?
+            .convert_source(builder)
+    }
+}
+
+/// Intermediate value of serialized data.
+#[derive(Debug)]
+enum SerValue {
+    Value(ConfigValue<'static>),
+    Array(Vec<SerValue>),
+    Map(Vec<(String, SerValue)>),
+    Empty,
+}
+
+impl ConfigSourceAdaptor for SerValue {
+    fn convert_source(self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        match self {
+            SerValue::Value(v) => builder.insert(v),
+            SerValue::Array(v) => builder.insert_array(v)?,
+            SerValue::Map(v) => builder.insert_map(v)?,
+            SerValue::Empty => {}
+        }
+        Ok(())
+    }
+}
+
+struct ValueSerializer;
+
+macro_rules! serialize_value {
+    ($($f:ident: $t:ty),+) => {$(
+        fn $f(self, v: $t) -> Result<SerValue, ConfigError> {
+            Ok(SerValue::Value(v.into()))
+        }
+    )+};
+}
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = SerValue;
+    type Error = ConfigError;
+    type SerializeSeq = SeqSerializer;
+    type SerializeTuple = SeqSerializer;
+    type SerializeTupleStruct = SeqSerializer;
+    type SerializeTupleVariant = VariantSerializer<SeqSerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = MapSerializer;
+    type SerializeStructVariant = VariantSerializer<MapSerializer>;
+
+    serialize_value!(
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+        serialize_f32: f32,
+        serialize_f64: f64
+    );
+
+    fn serialize_char(self, v: char) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Value(v.to_string().into()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Value(v.to_string().into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Array(
+            v.iter().map(|b| SerValue::Value((*b).into())).collect(),
+        ))
+    }
+
+    fn serialize_none(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Empty)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<SerValue, ConfigError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Empty)
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Empty)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<SerValue, ConfigError> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<SerValue, ConfigError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Map(vec![(
+            variant.to_string(),
+            value.serialize(self)?,
+        )]))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<SeqSerializer, ConfigError> {
+        Ok(SeqSerializer(Vec::with_capacity(len.unwrap_or(0))))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer, ConfigError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer, ConfigError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<SeqSerializer>, ConfigError> {
+        Ok(VariantSerializer(variant, self.serialize_seq(Some(len))?))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<MapSerializer, ConfigError> {
+        Ok(MapSerializer(Vec::with_capacity(len.unwrap_or(0)), None))
+    }
+
+    fn serialize_struct(self, _: &'static str, len: usize) -> Result<MapSerializer, ConfigError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<VariantSerializer<MapSerializer>, ConfigError> {
+        Ok(VariantSerializer(variant, self.serialize_map(Some(len))?))
+    }
+}
+
+struct SeqSerializer(Vec<SerValue>);
+
+impl SeqSerializer {
+    fn push<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), ConfigError> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), ConfigError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Array(self.0))
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), ConfigError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Array(self.0))
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), ConfigError> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Array(self.0))
+    }
+}
+
+struct MapSerializer(Vec<(String, SerValue)>, Option<String>);
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), ConfigError> {
+        self.1 = Some(key.serialize(KeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), ConfigError> {
+        let key = self
+            .1
+            .take()
+            .ok_or_else(|| <ConfigError as ser::Error>::custom("map value without key"))?;
+        self.0.push((key, value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Map(self.0))
+    }
+}
+
+impl ser::SerializeStruct for MapSerializer {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConfigError> {
+        self.0
+            .push((key.to_string(), value.serialize(ValueSerializer)?));
+        Ok(())
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Map(self.0))
+    }
+}
+
+struct VariantSerializer<S>(&'static str, S);
+
+impl ser::SerializeTupleVariant for VariantSerializer<SeqSerializer> {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), ConfigError> {
+        self.1.push(value)
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Map(vec![(
+            self.0.to_string(),
+            SerValue::Array(self.1.0),
+        )]))
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<MapSerializer> {
+    type Ok = SerValue;
+    type Error = ConfigError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), ConfigError> {
+        ser::SerializeStruct::serialize_field(&mut self.1, key, value)
+    }
+
+    fn end(self) -> Result<SerValue, ConfigError> {
+        Ok(SerValue::Map(vec![(
+            self.0.to_string(),
+            SerValue::Map(self.1.0),
+        )]))
+    }
+}
+
+/// Serialize map keys into string partial keys.
+struct KeySerializer;
+
+macro_rules! serialize_key {
+    ($($f:ident: $t:ty),+) => {$(
+        fn $f(self, v: $t) -> Result<String, ConfigError> {
+            Ok(v.to_string())
+        }
+    )+};
+}
+
+fn key_error() -> ConfigError {
+    <ConfigError as ser::Error>::custom("map key must be a string, integer, bool or char")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = ConfigError;
+    type SerializeSeq = Impossible<String, ConfigError>;
+    type SerializeTuple = Impossible<String, ConfigError>;
+    type SerializeTupleStruct = Impossible<String, ConfigError>;
+    type SerializeTupleVariant = Impossible<String, ConfigError>;
+    type SerializeMap = Impossible<String, ConfigError>;
+    type SerializeStruct = Impossible<String, ConfigError>;
+    type SerializeStructVariant = Impossible<String, ConfigError>;
+
+    serialize_key!(
+        serialize_bool: bool,
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+        serialize_char: char,
+        serialize_str: &str
+    );
+
+    fn serialize_f32(self, _: f32) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_f64(self, _: f64) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_bytes(self, _: &[u8]) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<String, ConfigError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<String, ConfigError> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> Result<String, ConfigError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<String, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, ConfigError> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, ConfigError> {
+        Err(key_error())
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use std::collections::{BTreeMap, HashMap};
+
+    use serde::Serialize;
+
+    use super::SerializeSource;
+    use crate::{
+        ConfigError, ConfigTree, Configuration,
+        test::{TestConfigExt, replace_test_suit},
+    };
+
+    #[derive(Serialize)]
+    struct Defaults {
+        app: App,
+    }
+
+    #[derive(Serialize)]
+    struct App {
+        name: &'static str,
+        port: u16,
+        ratio: f64,
+        debug: bool,
+        big: u64,
+        hosts: Vec<&'static str>,
+        opts: Vec<Option<u8>>,
+        missing: Option<String>,
+        labels: BTreeMap<&'static str, &'static str>,
+        mode: Mode,
+        level: Level,
+        unit: (),
+    }
+
+    #[derive(Serialize)]
+    enum Mode {
+        Fast,
+    }
+
+    #[derive(Serialize)]
+    enum Level {
+        Custom { value: u8 },
+    }
+
+    fn defaults() -> Defaults {
+        let mut labels = BTreeMap::new();
+        labels.insert("k1", "one");
+        Defaults {
+            app: App {
+                name: "demo",
+                port: 8080,
+                ratio: 0.5,
+                debug: true,
+                big: u64::MAX,
+                hosts: vec!["a", "b"],
+                opts: vec![Some(1), None, Some(3)],
+                missing: None,
+                labels,
+                mode: Mode::Fast,
+                level: Level::Custom { value: 3 },
+                unit: (),
+            },
+        }
+    }
+
+    #[test]
+    fn serialize_source_test() {
+        let source = SerializeSource::new("defaults", defaults());
+        assert_eq!(
+            "serialize:defaults",
+            crate::source::ConfigSource::name(&source)
+        );
+        let config = source.new_config();
+        let tree: ConfigTree = config.get("app.level").unwrap();
+        assert_eq!(r#"{"Custom":{"value":3}}"#, tree.to_string());
+        assert_eq!("demo", config.get::<String>("app.name").unwrap());
+        assert_eq!(8080, config.get::<u16>("app.port").unwrap());
+        assert_eq!(0.5, config.get::<f64>("app.ratio").unwrap());
+        assert!(config.get::<bool>("app.debug").unwrap());
+        assert_eq!(
+            vec!["a", "b"],
+            config.get::<Vec<String>>("app.hosts").unwrap()
+        );
+        assert_eq!("one", config.get::<String>("app.labels.k1").unwrap());
+        assert_eq!("Fast", config.get::<String>("app.mode").unwrap());
+        assert!(
+            config
+                .get::<Option<ConfigTree>>("app.unit")
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(u64::MAX, config.get::<u64>("app.big").unwrap());
+        assert_eq!(
+            vec![Some(1), None, Some(3)],
+            config.get::<Vec<Option<u8>>>("app.opts").unwrap()
+        );
+        assert_eq!(None, config.get::<Option<String>>("app.missing").unwrap());
+    }
+
+    #[test]
+    fn serialize_source_priority_test() {
+        let config = Configuration::new()
+            .register_kv("inline")
+            .set("app.port", 9090)
+            .finish()
+            .unwrap()
+            .register_source(SerializeSource::new("defaults", defaults()))
+            .unwrap();
+        assert_eq!(9090, config.get::<u16>("app.port").unwrap());
+        assert_eq!("demo", config.get::<String>("app.name").unwrap());
+    }
+
+    #[test]
+    fn serialize_source_error_test() {
+        let mut map = HashMap::new();
+        map.insert(vec![1u8], 1u8);
+        let r = Configuration::new().register_source(SerializeSource::new("bad", map));
+        assert!(matches!(r, Err(ConfigError::ConfigParseError(_, _))));
+    }
+
+    #[test]
+    fn replace_test() -> Result<(), ConfigError> {
+        let mut value = HashMap::new();
+        value.insert("a", HashMap::from([("_replace", "true"), ("x", "x")]));
+        value.insert("b", HashMap::from([("x", "x")]));
+        replace_test_suit(SerializeSource::new("serialize", value))
+    }
+}
@@ -1,8 +1,13 @@
 //! Toml config source.
 
-use super::{memory::ConfigSourceBuilder, ConfigSourceAdaptor, ConfigSourceParser};
+use super::{
+    line_column, memory::ConfigSourceBuilder, ConfigSourceAdaptor, ConfigSourceParser, PROFILE_KEY,
+};
 use crate::ConfigError;
-use toml::Value;
+use toml::{
+    de::{DeTable, DeValue},
+    Spanned, Value,
+};
 
 pub type Toml = Value;
 
@@ -24,23 +29,137 @@ impl ConfigSourceAdaptor for Toml {
 impl ConfigSourceParser for Toml {
     type Adaptor = Toml;
     fn parse_source(c: &str) -> Result<Self::Adaptor, ConfigError> {
-        toml::from_str::<Value>(c).map_err(ConfigError::from_cause)
+        toml::from_str::<Value>(c).map_err(|e| match e.span() {
+            Some(span) => {
+                let (line, column) = line_column(c, span.start);
+                ConfigError::from_cause_at(e, line, column)
+            }
+            None => ConfigError::from_cause(e),
+        })
     }
 
     fn file_extensions() -> Vec<&'static str> {
         vec!["toml", "tml"]
     }
+
+    fn split_profiles(mut v: Toml) -> Vec<(Option<String>, Toml)> {
+        let mut out = vec![];
+        if let Value::Table(t) = &mut v {
+            if let Some(Value::Table(profiles)) = t.get_mut(PROFILE_KEY) {
+                // Entries other than tables are kept, so that they are reported as unused keys.
+                let sections: Vec<String> = profiles
+                    .iter()
+                    .filter(|(_, v)| v.is_table())
+                    .map(|(k, _)| k.clone())
+                    .collect();
+                for k in sections {
+                    if let Some(v) = profiles.remove(&k) {
+                        out.push((Some(k), v));
+                    }
+                }
+                if profiles.is_empty() {
+                    t.remove(PROFILE_KEY);
+                }
+            }
+        }
+        out.insert(0, (None, v));
+        out
+    }
+
+    fn locate(c: &str) -> Vec<(String, usize, usize)> {
+        fn walk(c: &str, key: &str, v: &Spanned<DeValue<'_>>, out: &mut Vec<(String, usize, usize)>) {
+            match v.get_ref() {
+                DeValue::Table(t) => {
+                    for (k, v) in t {
+                        let key = format!("{}.{}", key, k.get_ref());
+                        let (line, column) = line_column(c, k.span().start);
+                        out.push((key.clone(), line, column));
+                        walk(c, &key, v, out);
+                    }
+                }
+                DeValue::Array(a) => {
+                    for (i, v) in a.into_iter().enumerate() {
+                        let key = format!("{}.{}", key, i);
+                        let (line, column) = line_column(c, v.span().start);
+                        out.push((key.clone(), line, column));
+                        walk(c, &key, v, out);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut out = vec![];
+        if let Ok(t) = DeTable::parse(c) {
+            let t = Spanned::new(t.span(), DeValue::Table(t.into_inner()));
+            walk(c, "", &t, &mut out);
+        }
+        out
+    }
 }
 
 #[cfg_attr(coverage_nightly, coverage(off))]
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{source::inline_source, test::source_test_suit};
+    use crate::{
+        inline_source_config,
+        source::inline_source,
+        test::{null_test_suit, replace_test_suit, source_test_suit},
+    };
 
     #[test]
     #[allow(unused_qualifications)]
     fn inline_test() -> Result<(), ConfigError> {
         source_test_suit(inline_source!("../../app.toml")?)
     }
+
+    #[test]
+    fn null_test() -> Result<(), ConfigError> {
+        null_test_suit(inline_source_config::<Toml>("toml".into(), "a._null = true\nb = { _null = true }\nd = \"x\"\ne._null = false")?)
+    }
+
+    #[test]
+    fn replace_test() -> Result<(), ConfigError> {
+        replace_test_suit(inline_source_config::<Toml>("toml".into(), "[a]\n_replace = true\nx = \"x\"\n[b]\nx = \"x\"")?)
+    }
+
+    #[test]
+    fn locate_test() {
+        let locations = Toml::locate("a = 1\n[b]\nc = [\"x\", { d = 2 }]\n");
+        assert_eq!(
+            vec![
+                (".a".to_string(), 1, 1),
+                (".b".to_string(), 2, 2),
+                (".b.c".to_string(), 3, 1),
+                (".b.c.0".to_string(), 3, 6),
+                (".b.c.1".to_string(), 3, 11),
+                (".b.c.1.d".to_string(), 3, 13),
+            ],
+            locations
+        );
+    }
+
+    #[test]
+    fn split_profiles_test() -> Result<(), ConfigError> {
+        let v = Toml::parse_source("a = 1\nprofile.x = 1\n_profiles.x = 1\n[_profiles.prod]\na = 2\n")?;
+        let v = Toml::split_profiles(v);
+        assert_eq!(2, v.len());
+        assert_eq!(
+            (None, &Toml::parse_source("a = 1\nprofile.x = 1\n_profiles.x = 1")?),
+            (v[0].0.clone(), &v[0].1)
+        );
+        assert_eq!(
+            (Some("prod".to_string()), &Toml::parse_source("a = 2")?),
+            (v[1].0.clone(), &v[1].1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let e = Toml::parse_source("a = 1\nb = = 2\n").err().unwrap();
+        assert!(matches!(e, ConfigError::ConfigCause(_)));
+        let c = e.context().unwrap();
+        assert_eq!((Some(2), Some(5)), (c.line, c.column));
+    }
 }
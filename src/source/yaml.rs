@@ -1,8 +1,15 @@
 //! Yaml config source.
-use yaml_rust2::YamlLoader;
+use yaml_rust2::{
+    parser::{Event, MarkedEventReceiver, Parser},
+    scanner::Marker,
+    YamlLoader,
+};
 
-use super::{memory::ConfigSourceBuilder, ConfigSourceAdaptor, ConfigSourceParser};
-use crate::ConfigError;
+use super::{
+    memory::ConfigSourceBuilder, split_profile_names, ConfigSourceAdaptor, ConfigSourceParser,
+    PROFILE_DIRECTIVE, PROFILE_KEY,
+};
+use crate::{ConfigError, ConfigValue};
 
 impl ConfigSourceAdaptor for yaml_rust2::Yaml {
     fn convert_source(self, source: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
@@ -11,6 +18,7 @@ impl ConfigSourceAdaptor for yaml_rust2::Yaml {
             yaml_rust2::Yaml::Integer(v) => source.insert(v),
             yaml_rust2::Yaml::String(v) => source.insert(v),
             yaml_rust2::Yaml::Boolean(v) => source.insert(v),
+            yaml_rust2::Yaml::Null => source.insert(ConfigValue::Null),
             yaml_rust2::Yaml::Array(v) => source.insert_array(v)?,
             yaml_rust2::Yaml::Hash(v) => source.insert_map(
                 v.into_iter()
@@ -35,13 +43,138 @@ impl ConfigSourceParser for Yaml {
     type Adaptor = Yaml;
     fn parse_source(content: &str) -> Result<Self::Adaptor, ConfigError> {
         Ok(Yaml(
-            YamlLoader::load_from_str(content).map_err(ConfigError::from_cause)?,
+            YamlLoader::load_from_str(content).map_err(|e| {
+                let (line, column) = (e.marker().line(), e.marker().col() + 1);
+                ConfigError::from_cause_at(e, line, column)
+            })?,
         ))
     }
 
     fn file_extensions() -> Vec<&'static str> {
         vec!["yaml", "yml"]
     }
+
+    fn split_profiles(v: Yaml) -> Vec<(Option<String>, Yaml)> {
+        let mut base = vec![];
+        let mut out = vec![];
+        for mut doc in v.0 {
+            let mut profiles = vec![];
+            if let yaml_rust2::Yaml::Hash(h) = &mut doc {
+                let key = yaml_rust2::Yaml::String(PROFILE_KEY.to_string());
+                if let Some(yaml_rust2::Yaml::Hash(sections)) = h.get_mut(&key) {
+                    // Entries other than hashes are kept, so that they are reported as unused keys.
+                    let names: Vec<yaml_rust2::Yaml> = sections
+                        .iter()
+                        .filter(|(k, v)| k.as_str().is_some() && v.as_hash().is_some())
+                        .map(|(k, _)| k.clone())
+                        .collect();
+                    for k in names {
+                        if let (Some(v), yaml_rust2::Yaml::String(k)) = (sections.remove(&k), k) {
+                            out.push((Some(k), Yaml(vec![v])));
+                        }
+                    }
+                    if sections.is_empty() {
+                        h.remove(&key);
+                    }
+                }
+                match h.remove(&yaml_rust2::Yaml::String(PROFILE_DIRECTIVE.to_string())) {
+                    Some(yaml_rust2::Yaml::String(v)) => {
+                        profiles.extend(split_profile_names(&v).map(|v| v.to_string()))
+                    }
+                    Some(yaml_rust2::Yaml::Array(v)) => profiles
+                        .extend(v.iter().filter_map(|v| v.as_str()).map(|v| v.to_string())),
+                    _ => {}
+                }
+            }
+            if profiles.is_empty() {
+                base.push(doc);
+                continue;
+            }
+            for p in profiles {
+                out.push((Some(p), Yaml(vec![doc.clone()])));
+            }
+        }
+        out.insert(0, (None, Yaml(base)));
+        out
+    }
+
+    fn locate(content: &str) -> Vec<(String, usize, usize)> {
+        let mut locator = Locator::default();
+        let _ = Parser::new_from_str(content).load(&mut locator, true);
+        locator.out
+    }
+}
+
+/// Collect locations of keys by parser events.
+#[derive(Default)]
+struct Locator {
+    /// Current path, and pending map key or next sequence index of each level.
+    stack: Vec<(String, Option<String>, Option<usize>)>,
+    out: Vec<(String, usize, usize)>,
+}
+
+impl Locator {
+    /// Key of next node, and whether it is a map key.
+    fn next_key(&mut self) -> Option<(String, bool)> {
+        let (path, key, index) = self.stack.last_mut()?;
+        Some(match (key.take(), index) {
+            (Some(k), _) => (format!("{}.{}", path, k), false),
+            (None, Some(i)) => {
+                *i += 1;
+                (format!("{}.{}", path, *i - 1), false)
+            }
+            (None, None) => (path.clone(), true),
+        })
+    }
+
+    /// Add location, map values are located at their keys.
+    fn push(&mut self, path: &str, mark: Marker) {
+        if self.out.last().is_none_or(|(k, ..)| k != path) {
+            self.out
+                .push((path.to_string(), mark.line(), mark.col() + 1));
+        }
+    }
+}
+
+impl MarkedEventReceiver for Locator {
+    fn on_event(&mut self, ev: Event, mark: Marker) {
+        match ev {
+            Event::DocumentStart => self.stack.clear(),
+            Event::Scalar(v, ..) => match self.next_key() {
+                Some((path, true)) => {
+                    self.push(&format!("{}.{}", path, v), mark);
+                    if let Some((_, key, _)) = self.stack.last_mut() {
+                        *key = Some(v);
+                    }
+                }
+                Some((path, false)) => self.push(&path, mark),
+                None => {}
+            },
+            Event::MappingStart(..) | Event::SequenceStart(..) => {
+                let path = match self.next_key() {
+                    Some((path, false)) => {
+                        self.push(&path, mark);
+                        path
+                    }
+                    // Complex keys are not supported.
+                    Some((path, true)) => format!("{}.?", path),
+                    None => String::new(),
+                };
+                let index = matches!(ev, Event::SequenceStart(..)).then_some(0);
+                self.stack.push((path, None, index));
+            }
+            Event::MappingEnd | Event::SequenceEnd => {
+                if let Some((path, ..)) = self.stack.pop() {
+                    if path.ends_with(".?") {
+                        if let Some((_, key, _)) = self.stack.last_mut() {
+                            *key = Some("?".to_string());
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 /// Yaml source.
@@ -52,11 +185,99 @@ pub struct Yaml(Vec<yaml_rust2::Yaml>);
 #[cfg(test)]
 mod test {
     use super::*;
-    use crate::{source::inline_source, test::source_test_suit};
+    use crate::{
+        source::{inline_source, memory::HashSource},
+        inline_source_config,
+        test::{TestConfigExt, null_test_suit, replace_test_suit, source_test_suit},
+    };
 
     #[test]
     #[allow(unused_qualifications)]
     fn inline_test() -> Result<(), ConfigError> {
         source_test_suit(inline_source!("../../app.yaml")?)
     }
+
+    #[test]
+    fn null_test() -> Result<(), ConfigError> {
+        null_test_suit(inline_source_config::<Yaml>("yaml".into(), "a: ~\nb: null\nd: x")?)
+    }
+
+    #[test]
+    fn replace_test() -> Result<(), ConfigError> {
+        replace_test_suit(inline_source_config::<Yaml>("yaml".into(), "a:\n  _replace: true\n  x: x\nb:\n  x: x")?)
+    }
+
+    #[test]
+    fn split_profiles_test() -> Result<(), ConfigError> {
+        let v = Yaml::parse_source(
+            "a: 1\n_profiles:\n  dev:\n    a: 2\n---\n_profile: prod, test\nb: 1\n",
+        )?;
+        let v = Yaml::split_profiles(v);
+        let names: Vec<Option<&str>> = v.iter().map(|(n, _)| n.as_deref()).collect();
+        assert_eq!(vec![None, Some("dev"), Some("prod"), Some("test")], names);
+        let mut source = HashSource::new("yaml");
+        let (_, prod) = v.into_iter().nth(2).unwrap();
+        prod.convert_source(&mut source.prefixed())?;
+        let config = source.new_config();
+        assert_eq!(1, config.get::<u8>("b")?);
+        assert_eq!(None, config.get::<Option<String>>("_profile")?);
+        assert_eq!(None, config.get::<Option<String>>("a")?);
+        Ok(())
+    }
+
+    #[test]
+    fn locate_test() {
+        let locations = Yaml::locate("a:\n  b: 1\n  c:\n    - x\n    - k: y\n---\nd: [1, 2]\n");
+        assert_eq!(
+            vec![
+                (".a".to_string(), 1, 1),
+                (".a.b".to_string(), 2, 3),
+                (".a.c".to_string(), 3, 3),
+                (".a.c.0".to_string(), 4, 7),
+                (".a.c.1".to_string(), 5, 8),
+                (".a.c.1.k".to_string(), 5, 7),
+                (".d".to_string(), 7, 1),
+                (".d.0".to_string(), 7, 5),
+                (".d.1".to_string(), 7, 8),
+            ],
+            locations
+        );
+    }
+
+    #[test]
+    fn explain_file_test() -> Result<(), ConfigError> {
+        let path = std::path::PathBuf::from("target/explain_test.yaml");
+        std::fs::write(&path, "app:\n  name: demo\n  hosts:\n    - a\n").map_err(ConfigError::from_cause)?;
+        let config = crate::Configuration::new().register_file(path.clone(), true)?;
+        let p = config.explain("app.hosts[0]");
+        let location = p.winner().unwrap().location.as_ref().unwrap();
+        assert_eq!(path, location.path);
+        assert_eq!((Some(4), Some(7)), (location.line, location.column));
+        let p = config.explain("app.name");
+        assert!(p.winner().unwrap().source.starts_with("file:"));
+        assert_eq!(
+            "target/explain_test.yaml:2:3",
+            p.winner().unwrap().location.as_ref().unwrap().to_string()
+        );
+        std::fs::remove_file(path).map_err(ConfigError::from_cause)?;
+        Ok(())
+    }
+
+    #[test]
+    fn parse_error_test() -> Result<(), ConfigError> {
+        let path = std::path::PathBuf::from("target/parse_error_test.yaml");
+        std::fs::write(&path, "app:\n  name: demo\n  hosts: [a\n").map_err(ConfigError::from_cause)?;
+        let e = crate::Configuration::new()
+            .register_file(path.clone(), true)
+            .err()
+            .unwrap();
+        assert!(matches!(e, ConfigError::ConfigCause(_)));
+        let c = e.context().unwrap();
+        assert_eq!(Some(&path), c.path.as_ref());
+        assert!(c.source.as_deref().unwrap().starts_with("file:"));
+        assert!(c.line.is_some() && c.column.is_some());
+        assert!(e.to_string().contains("at target/parse_error_test.yaml:"));
+        std::fs::remove_file(path).map_err(ConfigError::from_cause)?;
+        Ok(())
+    }
 }
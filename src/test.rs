@@ -45,6 +45,39 @@ struct IntSuit {
     v3: u32,
 }
 
+/// Check `_replace` directive, `src` sets `a._replace = true`, `a.x = "x"` and `b.x = "x"`.
+#[allow(dead_code)]
+pub(crate) fn replace_test_suit(src: impl ConfigSource + 'static) -> Result<(), ConfigError> {
+    let config = src
+        .new_config()
+        .register_kv("low")
+        .set("a.x", "low")
+        .set("a.y", "low")
+        .set("b.y", "low")
+        .finish()?;
+    let a = config.get::<HashMap<String, String>>("a")?;
+    assert_eq!(1, a.len());
+    assert_eq!("x", a["x"]);
+    assert_eq!("x", config.get::<String>("b.x")?);
+    assert_eq!("low", config.get::<String>("b.y")?);
+    Ok(())
+}
+
+/// Check nulled keys, `src` sets `a` and `b` to null and `d = "x"`.
+#[allow(dead_code)]
+pub(crate) fn null_test_suit(src: impl ConfigSource + 'static) -> Result<(), ConfigError> {
+    let config = src
+        .new_config()
+        .register_kv("low")
+        .set("a", "low")
+        .set("b.c", "low")
+        .finish()?;
+    assert_eq!(None, config.get::<Option<String>>("a")?);
+    assert_eq!(None, config.get::<Option<String>>("b.c")?);
+    assert_eq!("x", config.get::<String>("d")?);
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub(crate) fn source_test_suit(src: impl ConfigSource + 'static) -> Result<(), ConfigError> {
     let config = src.new_config();
@@ -158,7 +191,7 @@ fn validate_annotations_custom_error() {
         .new_config();
 
     let err = config.get_predefined::<ValidateCfg>().unwrap_err();
-    match err {
+    match err.kind() {
         ConfigError::ConfigParseError(key, _) => assert_eq!(key, "validate.threads"),
         _ => panic!("unexpected error: {:?}", err),
     }
@@ -179,7 +212,7 @@ fn validate_annotations_regex_email_error() {
         .new_config();
 
     let err = config.get_predefined::<ValidateCfg>().unwrap_err();
-    match err {
+    match err.kind() {
         ConfigError::ConfigParseError(key, _) => assert_eq!(key, "validate.user"),
         _ => panic!("unexpected error: {:?}", err),
     }
@@ -199,7 +232,7 @@ fn validate_annotations_not_empty_error() {
         .new_config();
 
     let err = config.get_predefined::<ValidateCfg>().unwrap_err();
-    match err {
+    match err.kind() {
         ConfigError::ConfigParseError(key, _) => assert_eq!(key, "validate.alias"),
         _ => panic!("unexpected error: {:?}", err),
     }
@@ -220,11 +253,11 @@ fn test_validate_multiple_rules_in_one_attribute() {
 
     let err =
         from_map::<MultiRuleValidation, _, _, _>(map, "").expect_err("expected validation failure");
-    match err {
+    match err.kind() {
         ConfigError::ConfigParseError(field, message) => {
             assert_eq!(field, "name");
             assert_eq!(message, "empty");
         }
-        other => panic!("unexpected error: {:?}", other),
+        _ => panic!("unexpected error: {:?}", err),
     }
 }
@@ -0,0 +1,363 @@
+//! Implementations of [`ToConfig`].
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6},
+    path::PathBuf,
+    time::Duration,
+};
+
+use crate::{
+    ConfigError, ConfigTree, ConfigValue, FromStrHolder, RefValue, ToConfig,
+    source::ConfigSourceBuilder,
+};
+
+/// Escape placeholder characters, so the value is read back as it is.
+fn escape_str(value: &str) -> ConfigValue<'static> {
+    let pat: &[_] = &['$', '\\', '}'];
+    if !value.contains(pat) {
+        return value.to_owned().into();
+    }
+    let mut buf = String::with_capacity(value.len() + 4);
+    for c in value.chars() {
+        if pat.contains(&c) {
+            buf.push('\\');
+        }
+        buf.push(c);
+    }
+    buf.into()
+}
+
+impl ToConfig for () {
+    #[inline]
+    fn to_config(&self, _: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        Ok(())
+    }
+}
+
+impl<V: ToConfig> ToConfig for Result<V, ConfigError> {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        match self {
+            Ok(v) => v.to_config(builder),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+impl<V: ToConfig> ToConfig for Option<V> {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        match self {
+            Some(v) => v.to_config(builder),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<V: ToConfig> ToConfig for Vec<V> {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        for (i, v) in self.iter().enumerate() {
+            builder.set_config(i, v)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: ToConfig, S> ToConfig for HashMap<String, V, S> {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        for (k, v) in self {
+            builder.set_config(k.as_str(), v)?;
+        }
+        Ok(())
+    }
+}
+
+impl<V: ToConfig> ToConfig for BTreeMap<String, V> {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        for (k, v) in self {
+            builder.set_config(k.as_str(), v)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToConfig for str {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        builder.insert(escape_str(self));
+        Ok(())
+    }
+}
+
+impl ToConfig for String {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.as_str().to_config(builder)
+    }
+}
+
+macro_rules! impl_value {
+    ($($x:ident),+) => {$(
+impl ToConfig for $x {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        builder.insert(*self);
+        Ok(())
+    }
+}
+    )+};
+}
+
+impl_value!(
+    bool, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64
+);
+
+macro_rules! impl_display {
+    ($($x:ident),+) => {$(
+impl ToConfig for $x {
+    #[inline]
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        builder.insert(self.to_string());
+        Ok(())
+    }
+}
+    )+};
+}
+
+impl_display!(
+    Ipv4Addr,
+    Ipv6Addr,
+    IpAddr,
+    SocketAddrV4,
+    SocketAddrV6,
+    SocketAddr
+);
+
+impl ToConfig for PathBuf {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.as_os_str().to_os_string().to_config(builder)
+    }
+}
+
+impl ToConfig for OsString {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        match self.to_str() {
+            Some(v) => v.to_config(builder),
+            None => Err(ConfigError::ConfigParseError(
+                builder.curr(),
+                format!("{:?} is not valid unicode", self),
+            )),
+        }
+    }
+}
+
+impl<V: ToString> ToConfig for FromStrHolder<V> {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.0.to_string().to_config(builder)
+    }
+}
+
+/// Write duration with the largest unit that keeps it exact.
+impl ToConfig for Duration {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        let nanos = self.subsec_nanos();
+        let v = if nanos == 0 {
+            format!("{}s", self.as_secs())
+        } else if nanos % 1_000_000 == 0 {
+            format!("{}ms", self.as_millis())
+        } else if nanos % 1_000 == 0 {
+            format!("{}us", self.as_micros())
+        } else {
+            format!("{}ns", self.as_nanos())
+        };
+        builder.insert(v);
+        Ok(())
+    }
+}
+
+impl ToConfig for Shutdown {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        builder.insert(match self {
+            Shutdown::Read => "read",
+            Shutdown::Write => "write",
+            Shutdown::Both => "both",
+        });
+        Ok(())
+    }
+}
+
+impl ToConfig for Ordering {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        builder.insert(match self {
+            Ordering::Less => "lt",
+            Ordering::Equal => "eq",
+            Ordering::Greater => "gt",
+        });
+        Ok(())
+    }
+}
+
+#[cfg(feature = "log")]
+mod log {
+    use crate::{ConfigError, ToConfig, source::ConfigSourceBuilder};
+    use log::*;
+
+    impl ToConfig for LevelFilter {
+        fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+            builder.insert(self.as_str().to_lowercase());
+            Ok(())
+        }
+    }
+
+    impl ToConfig for Level {
+        fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+            builder.insert(self.as_str().to_lowercase());
+            Ok(())
+        }
+    }
+}
+
+#[cfg(feature = "coarsetime")]
+impl ToConfig for coarsetime::Duration {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        Duration::from(*self).to_config(builder)
+    }
+}
+
+impl<T: ToConfig> ToConfig for RefValue<T> {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        self.with(|v| v.to_config(builder))?
+    }
+}
+
+impl ToConfig for ConfigTree {
+    fn to_config(&self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        match self {
+            ConfigTree::Value(ConfigValue::StrRef(v)) => v.to_config(builder),
+            ConfigTree::Value(ConfigValue::Str(v)) => v.to_config(builder),
+            ConfigTree::Value(v) => {
+                builder.insert(v.clone_static());
+                Ok(())
+            }
+            ConfigTree::Array(v) => v.to_config(builder),
+            ConfigTree::Map(v) => v.to_config(builder),
+        }
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use std::fmt::Debug;
+
+    use crate::{
+        Configuration, FromConfig, ToConfig, source::memory::HashSource, test::TestConfigExt,
+    };
+
+    use super::*;
+
+    fn round_trip<T: ToConfig + FromConfig + PartialEq + Debug>(value: T) {
+        let mut source = HashSource::new("to_config");
+        source.prefixed().set_config("key", &value).unwrap();
+        let config = source.new_config();
+        assert_eq!(value, config.get::<T>("key").unwrap());
+    }
+
+    #[test]
+    fn round_trip_value_test() {
+        round_trip(true);
+        round_trip(-8i8);
+        round_trip(u64::MAX);
+        round_trip(i128::MIN);
+        round_trip(1.1f32);
+        round_trip(0.1f64);
+        round_trip("hello".to_string());
+        round_trip("${a.b} \\ {x} $".to_string());
+        round_trip(PathBuf::from("C:\\Program Files\\app"));
+        round_trip(OsString::from("os"));
+        round_trip(Ipv4Addr::LOCALHOST);
+        round_trip(IpAddr::V6(Ipv6Addr::LOCALHOST));
+        round_trip("127.0.0.1:8080".parse::<SocketAddr>().unwrap());
+        round_trip(Shutdown::Write);
+        round_trip(Ordering::Greater);
+        round_trip(Duration::from_secs(5));
+        round_trip(Duration::from_millis(1500));
+        round_trip(Duration::from_micros(1));
+        round_trip(Duration::new(3, 7));
+        round_trip(Duration::MAX);
+        round_trip(Duration::new(u64::MAX, 1_000));
+        #[cfg(feature = "log")]
+        {
+            round_trip(::log::LevelFilter::Warn);
+            round_trip(::log::Level::Debug);
+        }
+    }
+
+    #[test]
+    fn round_trip_collection_test() {
+        round_trip(Some(1u8));
+        round_trip(vec!["a".to_string(), "b".to_string()]);
+        round_trip(vec![vec![1u8], vec![2, 3]]);
+        round_trip(
+            [("a".to_string(), 1u8), ("b".to_string(), 2)]
+                .into_iter()
+                .collect::<HashMap<_, _>>(),
+        );
+        round_trip(
+            [("x".to_string(), vec![true])]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>(),
+        );
+        round_trip(Vec::<u8>::new());
+    }
+
+    #[test]
+    fn write_empty_test() {
+        let mut source = HashSource::new("to_config");
+        let mut builder = source.prefixed();
+        builder.set_config("a", &None::<u8>).unwrap();
+        builder.set_config("b", &()).unwrap();
+        builder
+            .set_config("c", &Err::<u8, _>(ConfigError::TooManyInstances(1)))
+            .unwrap();
+        let config = source.new_config();
+        assert_eq!(None, config.get::<Option<u8>>("a").unwrap());
+        assert_eq!(None, config.get::<Option<u8>>("c").unwrap());
+    }
+
+    #[test]
+    fn write_tree_test() {
+        let config = HashSource::new("test")
+            .set("app.name", "demo")
+            .set("app.hosts[0]", "a")
+            .set("app.price", "\\${price\\}")
+            .new_config();
+        let tree: ConfigTree = config.get("app").unwrap();
+        let config = Configuration::new()
+            .register_kv("tree")
+            .set_config("copy", &tree)
+            .unwrap()
+            .finish()
+            .unwrap();
+        assert_eq!("demo", config.get::<String>("copy.name").unwrap());
+        assert_eq!("a", config.get::<String>("copy.hosts[0]").unwrap());
+        assert_eq!("${price}", config.get::<String>("copy.price").unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_non_unicode_test() {
+        use std::os::unix::ffi::OsStringExt;
+        let mut source = HashSource::new("to_config");
+        let v = OsString::from_vec(vec![0xff]);
+        match source.prefixed().set_config("a.b", &v) {
+            Err(ConfigError::ConfigParseError(k, _)) => assert_eq!("a.b", k),
+            _ => panic!("expected parse error"),
+        }
+    }
+}
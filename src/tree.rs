@@ -0,0 +1,305 @@
+//! Dynamic config tree.
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::{Display, Formatter, Write},
+};
+
+use crate::{
+    ConfigContext, ConfigError, ConfigValue, FromConfig,
+    key::{PartialKey, PartialKeyIter},
+    source::{ConfigSourceAdaptor, ConfigSourceBuilder},
+};
+
+/// Untyped config tree, useful for reading subtrees whose shape is unknown at compile time.
+///
+/// [`ConfigTree`] implements [`FromConfig`], so any key can be read as a tree:
+///
+/// ```rust
+/// use cfg_rs::*;
+/// let config = Configuration::new()
+///     .register_kv("plugin")
+///     .set("plugin.name", "demo")
+///     .set("plugin.hosts[0]", "a")
+///     .set("plugin.hosts[1]", "b")
+///     .finish()
+///     .unwrap();
+/// let tree: ConfigTree = config.get("plugin").unwrap();
+/// assert_eq!(r#"{"hosts":["a","b"],"name":"demo"}"#, tree.to_string());
+/// assert!(tree.get_path("hosts[1]").is_some());
+/// ```
+///
+/// Placeholders are resolved while reading, so the tree only contains final values.
+/// A key holding a value is read as [`ConfigTree::Value`] even if lower priority sources define sub keys.
+#[derive(Debug)]
+pub enum ConfigTree {
+    /// Leaf value.
+    Value(ConfigValue<'static>),
+    /// Array, built from index keys such as `key[0]`, missing indexes are [`ConfigValue::Null`].
+    Array(Vec<ConfigTree>),
+    /// Map, built from string keys such as `key.sub`.
+    Map(BTreeMap<String, ConfigTree>),
+}
+
+impl Clone for ConfigTree {
+    fn clone(&self) -> Self {
+        match self {
+            ConfigTree::Value(v) => ConfigTree::Value(v.clone_static()),
+            ConfigTree::Array(v) => ConfigTree::Array(v.clone()),
+            ConfigTree::Map(v) => ConfigTree::Map(v.clone()),
+        }
+    }
+}
+
+impl ConfigTree {
+    /// Get sub tree by relative config key, see [`crate::ConfigKey`] for the key's pattern details.
+    ///
+    /// Empty path returns the tree itself.
+    pub fn get_path(&self, path: &str) -> Option<&ConfigTree> {
+        let mut tree = self;
+        for key in PartialKeyIter::from(path) {
+            tree = match (tree, key) {
+                (ConfigTree::Array(v), PartialKey::Int(i)) => v.get(i)?,
+                (ConfigTree::Map(v), PartialKey::Str(k)) => v.get(k)?,
+                (ConfigTree::Map(v), PartialKey::Int(i)) => v.get(&i.to_string())?,
+                _ => return None,
+            };
+        }
+        Some(tree)
+    }
+
+    /// Get leaf value.
+    pub fn as_value(&self) -> Option<&ConfigValue<'static>> {
+        match self {
+            ConfigTree::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get array elements.
+    pub fn as_array(&self) -> Option<&[ConfigTree]> {
+        match self {
+            ConfigTree::Array(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Get map entries.
+    pub fn as_map(&self) -> Option<&BTreeMap<String, ConfigTree>> {
+        match self {
+            ConfigTree::Map(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl FromConfig for ConfigTree {
+    fn from_config(
+        context: &mut ConfigContext<'_>,
+        value: Option<ConfigValue<'_>>,
+    ) -> Result<Self, ConfigError> {
+        if let Some(v) = value {
+            return Ok(ConfigTree::Value(v.clone_static()));
+        }
+        let list = context.collect_keys();
+        if !list.str_key.is_empty() {
+            let mut map = BTreeMap::new();
+            for k in list.str_key {
+                // Keys removed by explicit null are skipped.
+                if let Some(t) = context.parse_config(k, None)? {
+                    map.insert(k.to_string(), t);
+                }
+            }
+            if let Some(v) = list.int_key {
+                for i in 0..v {
+                    if let Some(t) = context.do_parse_config(i, None, &mut HashSet::new())? {
+                        map.insert(i.to_string(), t);
+                    }
+                }
+            }
+            return Ok(ConfigTree::Map(map));
+        }
+        if let Some(v) = list.int_key {
+            let mut vs = Vec::with_capacity(v);
+            for i in 0..v {
+                // Gaps of sparse arrays, such as `a[1]` of `a[0]` and `a[2]`, are null.
+                let t: Option<ConfigTree> =
+                    context.do_parse_config(i, None, &mut HashSet::new())?;
+                vs.push(t.unwrap_or(ConfigTree::Value(ConfigValue::Null)));
+            }
+            return Ok(ConfigTree::Array(vs));
+        }
+        Err(context.not_found())
+    }
+}
+
+impl ConfigSourceAdaptor for ConfigTree {
+    fn convert_source(self, builder: &mut ConfigSourceBuilder<'_>) -> Result<(), ConfigError> {
+        match self {
+            ConfigTree::Value(v) => builder.insert(v),
+            ConfigTree::Array(v) => builder.insert_array(v)?,
+            ConfigTree::Map(v) => builder.insert_map(v)?,
+        }
+        Ok(())
+    }
+}
+
+fn write_str(f: &mut Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl Display for ConfigTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigTree::Value(v) => match v {
+                ConfigValue::StrRef(v) => write_str(f, v),
+                ConfigValue::Str(v) => write_str(f, v),
+                ConfigValue::Int(v) => write!(f, "{}", v),
+                ConfigValue::Float(v) if v.is_finite() => write!(f, "{:?}", v),
+                // NaN and infinity are not valid json numbers.
+                ConfigValue::Float(v) => write_str(f, &v.to_string()),
+                ConfigValue::BigInt(v) => write!(f, "{}", v),
+                ConfigValue::Decimal(v) => write!(f, "{}", v),
+                ConfigValue::Bool(v) => write!(f, "{}", v),
+                ConfigValue::Null => f.write_str("null"),
+                #[cfg(feature = "rand")]
+                ConfigValue::Rand(v) => write_str(f, &format!("{:?}", v)),
+            },
+            ConfigTree::Array(v) => {
+                f.write_char('[')?;
+                for (i, t) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", t)?;
+                }
+                f.write_char(']')
+            }
+            ConfigTree::Map(v) => {
+                f.write_char('{')?;
+                for (i, (k, t)) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_str(f, k)?;
+                    write!(f, ":{}", t)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+#[cfg_attr(coverage_nightly, coverage(off))]
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Configuration, source::memory::HashSource, test::TestConfigExt};
+
+    fn build_config() -> Configuration {
+        HashSource::new("test")
+            .set("app.name", "demo")
+            .set("app.port", 8080)
+            .set("app.ratio", 1.0)
+            .set("app.debug", true)
+            .set("app.desc", "${app.name} \"quoted\"")
+            .set("app.hosts[0]", "a")
+            .set("app.hosts[1]", "b")
+            .set("app.db[0].url", "x")
+            .new_config()
+    }
+
+    #[test]
+    fn tree_from_config_test() {
+        let config = build_config();
+        let tree: ConfigTree = config.get("app").unwrap();
+        assert_eq!(
+            r#"{"db":[{"url":"x"}],"debug":true,"desc":"demo \"quoted\"","hosts":["a","b"],"name":"demo","port":8080,"ratio":1.0}"#,
+            tree.to_string()
+        );
+        assert!(config.get::<ConfigTree>("not_exist").is_err());
+        assert!(
+            config
+                .get::<Option<ConfigTree>>("not_exist")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn tree_get_path_test() {
+        let tree: ConfigTree = build_config().get("app").unwrap();
+        assert_eq!("\"b\"", tree.get_path("hosts[1]").unwrap().to_string());
+        assert_eq!("\"x\"", tree.get_path("db[0].url").unwrap().to_string());
+        assert_eq!(2, tree.get_path("hosts").unwrap().as_array().unwrap().len());
+        assert!(tree.get_path("").unwrap().as_map().is_some());
+        assert!(matches!(
+            tree.get_path("port").unwrap().as_value(),
+            Some(ConfigValue::Int(8080))
+        ));
+        assert!(tree.get_path("hosts[2]").is_none());
+        assert!(tree.get_path("name.sub").is_none());
+        assert!(tree.get_path("hosts.sub").is_none());
+    }
+
+    #[test]
+    fn tree_mixed_keys_test() {
+        let config = HashSource::new("test")
+            .set("a.b", 1)
+            .set("a[0]", 2)
+            .set("c", "v")
+            .set("c.d", "ignored")
+            .new_config();
+        let tree: ConfigTree = config.get("a").unwrap();
+        assert_eq!(r#"{"0":2,"b":1}"#, tree.to_string());
+        assert_eq!("2", tree.get_path("[0]").unwrap().to_string());
+        let tree: ConfigTree = config.get("c").unwrap();
+        assert_eq!("\"v\"", tree.to_string());
+    }
+
+    #[test]
+    fn tree_sparse_array_test() {
+        let config = HashSource::new("test")
+            .set("a[0]", 1)
+            .set("a[2]", 3)
+            .new_config();
+        let tree: ConfigTree = config.get("a").unwrap();
+        assert_eq!("[1,null,3]", tree.to_string());
+        assert_eq!("3", tree.get_path("[2]").unwrap().to_string());
+    }
+
+    #[test]
+    fn tree_non_finite_float_test() {
+        let config = HashSource::new("test")
+            .set("a[0]", f64::NAN)
+            .set("a[1]", f64::INFINITY)
+            .set("a[2]", f64::NEG_INFINITY)
+            .set("a[3]", 0.5)
+            .new_config();
+        let tree: ConfigTree = config.get("a").unwrap();
+        assert_eq!(r#"["NaN","inf","-inf",0.5]"#, tree.to_string());
+    }
+
+    #[test]
+    fn tree_round_trip_test() {
+        let tree: ConfigTree = build_config().get("app").unwrap();
+        let text = tree.to_string();
+        let mut source = HashSource::new("tree");
+        tree.clone().convert_source(&mut source.prefixed()).unwrap();
+        let config = source.new_config();
+        let copy: ConfigTree = config.get("").unwrap();
+        assert_eq!(text, copy.to_string());
+        assert_eq!(8080u16, config.get::<u16>("port").unwrap());
+    }
+}
@@ -5,6 +5,7 @@ use std::{
     collections::{BTreeMap, HashMap, HashSet},
     ffi::OsString,
     net::{IpAddr, Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, SocketAddrV4, SocketAddrV6},
+    num::{ParseFloatError, ParseIntError},
     path::PathBuf,
     str::FromStr,
     time::Duration,
@@ -32,7 +33,30 @@ use crate::{ConfigContext, FromConfig, err::ConfigError};
 /// * `app.id` = `${random.u64}`
 /// * `app.instance` = `${app.name}-${app.id}`
 ///
-#[derive(Debug)]
+/// ## Placeholder can read values outside of configuration by [`crate::PlaceholderResolver`]s.
+///
+/// Built-in resolvers are enabled by [`crate::Configuration::register_builtin_resolvers`].
+///
+/// * `app.home` = `${env:HOME}`
+/// * `db.password` = `${file:/run/secrets/db_password}`
+///
+/// ## Placeholder can evaluate expressions, enabled by [`crate::Configuration::set_expressions`].
+///
+/// * `admin.port` = `${server.port + 1}`
+/// * `log.level` = `${env == 'prod' ? 'warn' : 'debug'}`
+///
+/// ## Placeholder can reference a whole subtree by `${@key}`.
+///
+/// The value must be the reference alone. Keys under the referencing key read through the referenced subtree,
+/// keys defined under the referencing key override it, so structs, [`Vec`] and maps can inherit from another key.
+/// Cyclic references lead to [`crate::ConfigError::ConfigRecursiveError`].
+///
+/// * `db.primary.host` = `10.0.0.1`
+/// * `db.primary.port` = `5432`
+/// * `db.replica` = `${@db.primary}`
+/// * `db.replica.host` = `10.0.0.2`, so `db.replica.port` is `5432`.
+///
+#[derive(Debug, Clone)]
 pub enum ConfigValue<'a> {
     /// String reference, supports placeholder expression.
     StrRef(&'a str),
@@ -42,8 +66,17 @@ pub enum ConfigValue<'a> {
     Int(i64),
     /// Float value.
     Float(f64),
+    /// Big integer, out of range of [`i64`].
+    BigInt(i128),
+    /// Decimal number in exact text form, used when [`f64`] cannot hold it without loss, such as `0.12345678901234567890`.
+    Decimal(String),
     /// Bool value.
     Bool(bool),
+    /// Explicit null, removes the key and its sub keys set by lower priority sources.
+    ///
+    /// Explicit null is read as absent, so [`Option`] fields are `None` and default values apply.
+    /// It is produced by json `null`, yaml `~`, and directive `key._null = true` for formats without null, such as toml.
+    Null,
     #[cfg(feature = "rand")]
     /// Random value.
     Rand(RandValue),
@@ -74,7 +107,10 @@ impl ConfigValue<'_> {
             ConfigValue::Str(v) => ConfigValue::Str(v.to_string()),
             ConfigValue::Int(v) => ConfigValue::Int(*v),
             ConfigValue::Float(v) => ConfigValue::Float(*v),
+            ConfigValue::BigInt(v) => ConfigValue::BigInt(*v),
+            ConfigValue::Decimal(v) => ConfigValue::Decimal(v.to_string()),
             ConfigValue::Bool(v) => ConfigValue::Bool(*v),
+            ConfigValue::Null => ConfigValue::Null,
             #[cfg(feature = "rand")]
             ConfigValue::Rand(v) => ConfigValue::Rand(*v),
         }
@@ -110,11 +146,15 @@ into_config_value_le!(Float = f64: f32, f64);
 macro_rules! into_config_value_u {
     ($($x:ident),*) => {$(
         impl From<$x> for ConfigValue<'_> {
+            #[allow(trivial_numeric_casts)]
             fn from(c: $x) -> Self {
                 if c <= i64::MAX as $x {
                     return ConfigValue::Int(c as i64);
                 }
-                ConfigValue::Str(c.to_string())
+                if c as u128 <= i128::MAX as u128 {
+                    return ConfigValue::BigInt(c as i128);
+                }
+                ConfigValue::Decimal(c.to_string())
             }
         })*
     };
@@ -125,11 +165,12 @@ into_config_value_u!(u64, u128, usize);
 macro_rules! into_config_value {
     ($($x:ident),*) => {$(
         impl From<$x> for ConfigValue<'_> {
+            #[allow(trivial_numeric_casts)]
             fn from(c: $x) -> Self {
                 if c <= i64::MAX as $x && c>= i64::MIN as $x {
                     return ConfigValue::Int(c as i64);
                 }
-                ConfigValue::Str(c.to_string())
+                ConfigValue::BigInt(c as i128)
             }
         })*
     };
@@ -174,7 +215,7 @@ impl<V: FromConfig> FromConfig for Option<V> {
         value: Option<ConfigValue<'_>>,
     ) -> Result<Self, ConfigError> {
         match V::from_config(context, value) {
-            Err(ConfigError::ConfigNotFound(_)) => Ok(None),
+            Err(err) if matches!(err.kind(), ConfigError::ConfigNotFound(_)) => Ok(None),
             Err(err) => Err(err),
             Ok(v) => Ok(Some(v)),
         }
@@ -270,6 +311,40 @@ impl<V: FromValue> FromConfig for V {
     }
 }
 
+/// Coercion policy, decides which [`ConfigValue`]s can be converted into the requested type.
+///
+/// | Target | `Strict` | `Default` | `Lenient` |
+/// |---|---|---|---|
+/// | `String` | strings | any scalar | any scalar |
+/// | integers | numbers without fraction | also numeric strings | also trimmed strings such as `" 1e3 "`, and bools as `0`/`1` |
+/// | floats | numbers | also numeric strings | also trimmed strings, and bools as `0`/`1` |
+/// | `bool` | bools | also `true`/`false`/`yes`/`no` | also `on`/`off`/`y`/`n`/`t`/`f`/`1`/`0` and integers `0`/`1` |
+/// | [`FromStringValue`] types | strings | strings | any scalar |
+///
+/// Default values of derived fields and values resolved from placeholders are text by construction,
+/// so they are converted with `Default` policy when `Strict` is configured.
+/// Custom [`FromValue`] implementations can read current policy by [`ConfigContext::coercion_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CoercionPolicy {
+    /// Only accept values whose types match the requested type.
+    Strict,
+    /// Accept strings for numbers and bools, and any scalar for strings.
+    #[default]
+    Default,
+    /// Accept more literals and trim strings before parsing.
+    Lenient,
+}
+
+impl std::fmt::Display for CoercionPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoercionPolicy::Strict => write!(f, "strict"),
+            CoercionPolicy::Default => write!(f, "default"),
+            CoercionPolicy::Lenient => write!(f, "lenient"),
+        }
+    }
+}
+
 impl FromValue for String {
     #[inline]
     fn from_value(
@@ -279,9 +354,15 @@ impl FromValue for String {
         let v = match value {
             ConfigValue::StrRef(s) => s.to_owned(),
             ConfigValue::Str(s) => s,
+            value if context.coercion_policy() == CoercionPolicy::Strict => {
+                return Err(context.type_mismatch::<String>(&value));
+            }
             ConfigValue::Int(s) => s.to_string(),
             ConfigValue::Float(s) => check_f64(context, s)?.to_string(),
+            ConfigValue::BigInt(s) => s.to_string(),
+            ConfigValue::Decimal(s) => s,
             ConfigValue::Bool(s) => s.to_string(),
+            ConfigValue::Null => return Err(context.type_mismatch::<String>(&ConfigValue::Null)),
             #[cfg(feature = "rand")]
             _ => return Err(context.parse_error("ConfigValueError")),
         };
@@ -309,6 +390,12 @@ impl<V: FromStringValue> FromValue for V {
         match value {
             ConfigValue::StrRef(s) => V::from_str_value(context, s),
             ConfigValue::Str(s) => V::from_str_value(context, &s),
+            #[cfg(feature = "rand")]
+            value @ ConfigValue::Rand(_) => Err(context.type_mismatch::<V>(&value)),
+            value if context.coercion_policy() == CoercionPolicy::Lenient => {
+                let s = String::from_value(context, value)?;
+                V::from_str_value(context, &s)
+            }
             value => Err(context.type_mismatch::<V>(&value)),
         }
     }
@@ -316,6 +403,13 @@ impl<V: FromStringValue> FromValue for V {
 
 #[inline]
 fn bool_from_str_value(context: &mut ConfigContext<'_>, value: &str) -> Result<bool, ConfigError> {
+    if context.coercion_policy() == CoercionPolicy::Lenient {
+        match &value.trim().to_lowercase()[..] {
+            "true" | "yes" | "on" | "y" | "t" | "1" => return Ok(true),
+            "false" | "no" | "off" | "n" | "f" | "0" => return Ok(false),
+            _ => {}
+        }
+    }
     match &value.to_lowercase()[..] {
         "true" | "yes" => Ok(true),
         "false" | "no" => Ok(false),
@@ -329,10 +423,16 @@ impl FromValue for bool {
         context: &mut ConfigContext<'_>,
         value: ConfigValue<'_>,
     ) -> Result<Self, ConfigError> {
+        let policy = context.coercion_policy();
         match value {
+            ConfigValue::Bool(s) => Ok(s),
+            ConfigValue::StrRef(_) | ConfigValue::Str(_) if policy == CoercionPolicy::Strict => {
+                Err(context.type_mismatch::<bool>(&value))
+            }
             ConfigValue::StrRef(s) => bool_from_str_value(context, s),
             ConfigValue::Str(s) => bool_from_str_value(context, &s),
-            ConfigValue::Bool(s) => Ok(s),
+            ConfigValue::Int(0) if policy == CoercionPolicy::Lenient => Ok(false),
+            ConfigValue::Int(1) if policy == CoercionPolicy::Lenient => Ok(true),
             value => Err(context.type_mismatch::<bool>(&value)),
         }
     }
@@ -365,7 +465,7 @@ impl_str_value!(
 #[allow(missing_debug_implementations)]
 pub struct FromStrHolder<V>(pub V);
 
-impl<V: FromStr<Err = E> + 'static, E: std::error::Error + 'static> FromStringValue
+impl<V: FromStr<Err = E> + 'static, E: std::error::Error + Send + Sync + 'static> FromStringValue
     for FromStrHolder<V>
 {
     #[inline]
@@ -376,19 +476,108 @@ impl<V: FromStr<Err = E> + 'static, E: std::error::Error + 'static> FromStringVa
     }
 }
 
+/// Convert float into integer, fails if it has fraction or is out of range.
+#[inline]
+pub(crate) fn float_to_int<T: TryFrom<i128> + TryFrom<u128>>(f: f64) -> Option<T> {
+    if f.fract() != 0.0 {
+        return None;
+    }
+    if f >= 0.0 {
+        if f < u128::MAX as f64 {
+            return T::try_from(f as u128).ok();
+        }
+    } else if f >= i128::MIN as f64 {
+        return T::try_from(f as i128).ok();
+    }
+    None
+}
+
+/// Convert decimal text into integer, fails if it has non-zero fraction or is out of range.
+fn decimal_to_int<T: TryFrom<i128> + TryFrom<u128>>(s: &str) -> Option<T> {
+    let (neg, s) = match s.strip_prefix('-') {
+        Some(v) => (true, v),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (num, mut exp) = match s.find(['e', 'E']) {
+        Some(pos) => (&s[..pos], s[pos + 1..].parse::<i32>().ok()?),
+        None => (s, 0),
+    };
+    let (int, frac) = num.split_once('.').unwrap_or((num, ""));
+    if int.is_empty() && frac.is_empty() {
+        return None;
+    }
+    let mut digits = format!("{}{}", int, frac);
+    exp = exp.checked_sub(frac.len() as i32)?;
+    while exp < 0 && digits.ends_with('0') {
+        digits.pop();
+        exp += 1;
+    }
+    if exp < 0 && digits.bytes().any(|c| c != b'0') {
+        return None;
+    }
+    let mut v: u128 = 0;
+    for c in digits.bytes() {
+        if !c.is_ascii_digit() {
+            return None;
+        }
+        v = v.checked_mul(10)?.checked_add((c - b'0') as u128)?;
+    }
+    if v != 0 {
+        for _ in 0..exp.max(0) {
+            v = v.checked_mul(10)?;
+        }
+    }
+    if !neg || v == 0 {
+        return T::try_from(v).ok();
+    }
+    let v = i128::try_from(v - 1).ok()?;
+    T::try_from(-v - 1).ok()
+}
+
+#[inline]
+fn int_from_str<T: FromStr<Err = ParseIntError> + TryFrom<i128> + TryFrom<u128>>(
+    policy: CoercionPolicy,
+    s: &str,
+) -> Result<T, ConfigError> {
+    if policy == CoercionPolicy::Lenient {
+        let s = s.trim();
+        return s
+            .parse::<T>()
+            .or_else(|e| decimal_to_int(s).ok_or_else(|| ConfigError::from_cause(e)));
+    }
+    s.parse::<T>().map_err(ConfigError::from_cause)
+}
+
+#[inline]
+fn int_from_value<T>(
+    context: &mut ConfigContext<'_>,
+    value: ConfigValue<'_>,
+) -> Result<T, ConfigError>
+where
+    T: FromStr<Err = ParseIntError> + TryFrom<i64> + TryFrom<i128> + TryFrom<u128> + Any,
+{
+    let policy = context.coercion_policy();
+    let v = match &value {
+        ConfigValue::StrRef(_) | ConfigValue::Str(_) if policy == CoercionPolicy::Strict => None,
+        ConfigValue::StrRef(s) => return int_from_str(policy, s),
+        ConfigValue::Str(s) => return int_from_str(policy, s),
+        ConfigValue::Int(s) => T::try_from(*s).ok(),
+        ConfigValue::BigInt(s) => T::try_from(*s).ok(),
+        ConfigValue::Float(_) if policy == CoercionPolicy::Strict => None,
+        ConfigValue::Float(s) => float_to_int(check_f64(context, *s)?),
+        ConfigValue::Decimal(s) => decimal_to_int(s),
+        ConfigValue::Bool(s) if policy == CoercionPolicy::Lenient => T::try_from(*s as i64).ok(),
+        _ => None,
+    };
+    v.ok_or_else(|| context.type_mismatch::<T>(&value))
+}
+
 macro_rules! impl_integer {
     ($($x:ident),+) => {$(
 impl FromValue for $x {
     #[inline]
     fn from_value(context: &mut ConfigContext<'_>, value: ConfigValue<'_>) -> Result<Self, ConfigError> {
-        use std::convert::TryFrom;
-        match value {
-            ConfigValue::StrRef(s) => Ok(s.parse::<$x>().map_err(ConfigError::from_cause)?),
-            ConfigValue::Str(s) => Ok(s.parse::<$x>().map_err(ConfigError::from_cause)?),
-            ConfigValue::Int(s) => Ok($x::try_from(s).map_err(ConfigError::from_cause)?),
-            ConfigValue::Float(s) => Ok(check_f64(context, s)? as $x),
-            _ => Err(context.type_mismatch::<$x>(&value)),
-        }
+        int_from_value(context, value)
     }
 }
     )+};
@@ -406,17 +595,37 @@ fn check_f64(context: &mut ConfigContext<'_>, f: f64) -> Result<f64, ConfigError
         Err(context.parse_error("infinite"))
     }
 }
+#[inline]
+fn float_from_str<T: FromStr<Err = ParseFloatError>>(
+    policy: CoercionPolicy,
+    s: &str,
+) -> Result<T, ConfigError> {
+    let s = if policy == CoercionPolicy::Lenient {
+        s.trim()
+    } else {
+        s
+    };
+    s.parse::<T>().map_err(ConfigError::from_cause)
+}
+
 macro_rules! impl_float {
     ($($x:ident),+) => {$(
 impl FromValue for $x {
     #[inline]
     #[allow(trivial_numeric_casts)]
     fn from_value(context: &mut ConfigContext<'_>, value: ConfigValue<'_>) -> Result<Self, ConfigError> {
+        let policy = context.coercion_policy();
         match value {
-            ConfigValue::StrRef(s) => Ok(s.parse::<$x>().map_err(ConfigError::from_cause)?),
-            ConfigValue::Str(s) => Ok(s.parse::<$x>().map_err(ConfigError::from_cause)?),
+            ConfigValue::StrRef(_) | ConfigValue::Str(_) if policy == CoercionPolicy::Strict => {
+                Err(context.type_mismatch::<$x>(&value))
+            }
+            ConfigValue::StrRef(s) => float_from_str(policy, s),
+            ConfigValue::Str(s) => float_from_str(policy, &s),
             ConfigValue::Int(s) => Ok(s as $x),
+            ConfigValue::BigInt(s) => Ok(s as $x),
             ConfigValue::Float(s) => Ok(check_f64(context, s)? as $x),
+            ConfigValue::Decimal(s) => Ok(s.parse::<$x>().map_err(ConfigError::from_cause)?),
+            ConfigValue::Bool(s) if policy == CoercionPolicy::Lenient => Ok(if s { 1.0 } else { 0.0 }),
             _ => Err(context.type_mismatch::<$x>(&value)),
         }
     }
@@ -431,8 +640,8 @@ fn parse_duration_from_str(
     context: &mut ConfigContext<'_>,
     du: &str,
 ) -> Result<Duration, ConfigError> {
-    let mut i = 0;
-    let mut multi = 1;
+    let mut i: u128 = 0;
+    let mut multi = Some(1u128);
     let mut last = None;
     for c in du.chars().rev() {
         match c {
@@ -450,21 +659,35 @@ fn parse_duration_from_str(
                 if last.is_none() {
                     last = Some('s');
                 }
-                i += multi * (c as u64 - '0' as u64);
-                multi *= 10;
+                let d = c as u128 - '0' as u128;
+                if d > 0 {
+                    i = match multi
+                        .and_then(|m| m.checked_mul(d))
+                        .and_then(|v| v.checked_add(i))
+                    {
+                        Some(v) => v,
+                        None => return Err(context.parse_error(du)),
+                    };
+                }
+                multi = multi.and_then(|m| m.checked_mul(10));
             }
             _ => return Err(context.parse_error(du)),
         }
     }
-    Ok(match last.unwrap_or('s') {
-        'h' => Duration::new(i * 3600, 0),
-        'M' => Duration::new(i * 60, 0),
-        's' => Duration::from_secs(i),
-        'm' => Duration::from_millis(i),
-        'u' => Duration::from_micros(i),
-        'n' => Duration::from_nanos(i),
+    // Split into seconds and nanoseconds, seconds must fit in u64.
+    let (secs, nanos) = match last.unwrap_or('s') {
+        'h' => (i.checked_mul(3600), 0),
+        'M' => (i.checked_mul(60), 0),
+        's' => (Some(i), 0),
+        'm' => (Some(i / 1_000), (i % 1_000) * 1_000_000),
+        'u' => (Some(i / 1_000_000), (i % 1_000_000) * 1_000),
+        'n' => (Some(i / 1_000_000_000), i % 1_000_000_000),
         _ => return Err(context.parse_error(du)),
-    })
+    };
+    match secs.and_then(|v| u64::try_from(v).ok()) {
+        Some(secs) => Ok(Duration::new(secs, nanos as u32)),
+        None => Err(context.parse_error(du)),
+    }
 }
 
 impl FromValue for Duration {
@@ -472,11 +695,20 @@ impl FromValue for Duration {
         context: &mut ConfigContext<'_>,
         value: ConfigValue<'_>,
     ) -> Result<Self, ConfigError> {
+        let policy = context.coercion_policy();
         match value {
+            ConfigValue::Str(du) if policy == CoercionPolicy::Lenient => {
+                parse_duration_from_str(context, du.trim())
+            }
+            ConfigValue::StrRef(du) if policy == CoercionPolicy::Lenient => {
+                parse_duration_from_str(context, du.trim())
+            }
             ConfigValue::Str(du) => parse_duration_from_str(context, &du),
             ConfigValue::StrRef(du) => parse_duration_from_str(context, du),
             ConfigValue::Int(seconds) => Ok(Duration::from_secs(seconds as u64)),
-            ConfigValue::Float(sec) => Ok(Duration::new(1, 0).mul_f64(sec)),
+            ConfigValue::Float(sec) if policy != CoercionPolicy::Strict => {
+                Ok(Duration::new(1, 0).mul_f64(sec))
+            }
             _ => Err(context.type_mismatch::<Self>(&value)),
         }
     }
@@ -558,7 +790,7 @@ pub mod time {
 #[cfg_attr(coverage_nightly, coverage(off))]
 #[cfg(test)]
 mod test {
-    use crate::{Configuration, key::CacheString};
+    use crate::{Configuration, key::CacheString, source::memory::HashSource, test::TestConfigExt};
 
     use super::*;
 
@@ -688,11 +920,13 @@ mod test {
         ($($fun:ident. $t:ty),+) => {
             $(
             #[quickcheck]
+            #[allow(trivial_numeric_casts)]
             fn $fun(i: $t) {
                 let v: ConfigValue<'static> = i.into();
                 match v {
                     ConfigValue::Int(_) => assert_eq!(true,  i <= i64::MAX as $t),
-                    ConfigValue::Str(_) => assert_eq!(true, i > i64::MAX as $t),
+                    ConfigValue::BigInt(_) => assert_eq!(true, i > i64::MAX as $t),
+                    ConfigValue::Decimal(_) => assert_eq!(true, i as u128 > i128::MAX as u128),
                     _ => assert_eq!(true, false),
                 }
             }
@@ -710,7 +944,7 @@ mod test {
                 let v: ConfigValue<'static> = i.into();
                 match v {
                     ConfigValue::Int(_) => assert_eq!(true,  i <= i64::MAX as $t && i>= i64::MIN as $t),
-                    ConfigValue::Str(_) => assert_eq!(true, i > i64::MAX as $t || i< i64::MIN as $t),
+                    ConfigValue::BigInt(_) => assert_eq!(true, i > i64::MAX as $t || i< i64::MIN as $t),
                     _ => assert_eq!(true, false),
                 }
             }
@@ -740,16 +974,18 @@ mod test {
     #[quickcheck]
     fn f64_tests(i: f64) {
         let mut context = TestContext::new();
-        should_valid!(context: i as u8 => i.is_finite());
-        should_valid!(context: i as u16 => i.is_finite());
-        should_valid!(context: i as u32 => i.is_finite());
-        should_valid!(context: i as u64 => i.is_finite());
-        should_valid!(context: i as u128 => i.is_finite());
-        should_valid!(context: i as i8 => i.is_finite());
-        should_valid!(context: i as i16 => i.is_finite());
-        should_valid!(context: i as i32 => i.is_finite());
-        should_valid!(context: i as i64 => i.is_finite());
-        should_valid!(context: i as i128 => i.is_finite());
+        let fit =
+            |min: f64, max: f64| i.is_finite() && i.fract() == 0.0 && i >= min && i < max + 1.0;
+        should_valid!(context: i as u8 => fit(0.0, u8::MAX as f64));
+        should_valid!(context: i as u16 => fit(0.0, u16::MAX as f64));
+        should_valid!(context: i as u32 => fit(0.0, u32::MAX as f64));
+        should_valid!(context: i as u64 => fit(0.0, u64::MAX as f64));
+        should_valid!(context: i as u128 => fit(0.0, u128::MAX as f64));
+        should_valid!(context: i as i8 => fit(i8::MIN as f64, i8::MAX as f64));
+        should_valid!(context: i as i16 => fit(i16::MIN as f64, i16::MAX as f64));
+        should_valid!(context: i as i32 => fit(i32::MIN as f64, i32::MAX as f64));
+        should_valid!(context: i as i64 => fit(i64::MIN as f64, i64::MAX as f64));
+        should_valid!(context: i as i128 => fit(i128::MIN as f64, i128::MAX as f64));
         should_valid!(context: i as f32 => i.is_finite());
         should_valid!(context: i as f64 => i.is_finite());
     }
@@ -1053,6 +1289,147 @@ mod test {
         assert!(v.is_err());
     }
 
+    #[test]
+    fn lossless_number_test() {
+        let mut context = TestContext::new();
+        assert_eq!(u64::MAX, context.read::<u64>(u64::MAX).unwrap());
+        assert_eq!(u128::MAX, context.read::<u128>(u128::MAX).unwrap());
+        assert_eq!(i128::MIN, context.read::<i128>(i128::MIN).unwrap());
+        assert_eq!(3, context.read::<u8>(3.0f64).unwrap());
+        assert_eq!(-3, context.read::<i8>(-3.0f64).unwrap());
+        assert_eq!(
+            100_000_000_000_000_000_000,
+            context.read::<u128>(1e20f64).unwrap()
+        );
+        assert_eq!(
+            u64::MAX.to_string(),
+            context.read::<String>(u64::MAX).unwrap()
+        );
+
+        let decimal = |v: &str| ConfigValue::Decimal(v.to_string());
+        assert_eq!(125, context.read::<u8>(decimal("12.50e1")).unwrap());
+        assert_eq!(-1, context.read::<i8>(decimal("-1.000")).unwrap());
+        assert_eq!(
+            i128::MIN,
+            context
+                .read::<i128>(decimal("-170141183460469231731687303715884105728"))
+                .unwrap()
+        );
+        assert_eq!(
+            "0.1234567890123456789",
+            context
+                .read::<String>(decimal("0.1234567890123456789"))
+                .unwrap()
+        );
+        assert_eq!(0.5, context.read::<f64>(decimal("0.5")).unwrap());
+
+        macro_rules! should_mismatch {
+            ($val:expr => $x:ty) => {
+                match context.read::<$x>($val).as_ref().map_err(ConfigError::kind) {
+                    Err(ConfigError::ConfigTypeMismatch(_, _, expected)) => {
+                        assert_eq!(std::any::type_name::<$x>(), *expected)
+                    }
+                    v => panic!("expected mismatch, got {:?}", v),
+                }
+            };
+        }
+        should_mismatch!(3.7f64 => u8);
+        should_mismatch!(256.0f64 => u8);
+        should_mismatch!(-1.0f64 => u32);
+        should_mismatch!(1e40f64 => u128);
+        should_mismatch!(300i64 => u8);
+        should_mismatch!(u64::MAX => i64);
+        should_mismatch!(decimal("1.5") => u8);
+        should_mismatch!(decimal("1e40") => u128);
+        should_mismatch!(decimal("abc") => u8);
+    }
+
+    #[test]
+    fn coercion_policy_test() {
+        #[derive(Debug, FromConfig)]
+        #[config(crate = "crate")]
+        struct App {
+            #[config(default = 8080)]
+            port: u16,
+            #[config(default = true)]
+            enabled: bool,
+        }
+
+        let config = HashSource::new("test")
+            .set("str_int", "8080")
+            .set("str_float", " 1.5 ")
+            .set("str_exp", "1e3")
+            .set("str_on", "on")
+            .set("str_yes", "yes")
+            .set("int", 1)
+            .set("float", 3.0)
+            .set("ref", "${int}")
+            .set("addr", "127.0.0.1")
+            .set("du", "5s")
+            .new_config();
+
+        let config = config.set_coercion_policy(CoercionPolicy::Strict);
+        assert!(config.get::<u16>("str_int").is_err());
+        assert!(config.get::<bool>("str_yes").is_err());
+        assert!(config.get::<f64>("str_float").is_err());
+        assert!(config.get::<String>("int").is_err());
+        assert!(config.get::<u8>("float").is_err());
+        assert_eq!(1, config.get::<u8>("int").unwrap());
+        assert_eq!(3.0, config.get::<f64>("float").unwrap());
+        assert_eq!(1, config.get::<u8>("ref").unwrap());
+        assert_eq!("1", config.get::<String>("ref").unwrap());
+        assert_eq!(Duration::new(5, 0), config.get::<Duration>("du").unwrap());
+        assert!(config.get::<Ipv4Addr>("addr").is_ok());
+        let app: App = config.get("app").unwrap();
+        assert_eq!(8080, app.port);
+        assert!(app.enabled);
+        match config
+            .get::<u16>("str_int")
+            .as_ref()
+            .map_err(ConfigError::kind)
+        {
+            Err(ConfigError::ConfigTypeMismatch(key, found, expected)) => {
+                assert_eq!("str_int", key);
+                assert_eq!("u16", *expected);
+                assert_eq!("String", *found);
+            }
+            v => panic!("expected mismatch, got {:?}", v),
+        }
+        let e = config.get::<u16>("str_int").unwrap_err();
+        assert_eq!(Some(CoercionPolicy::Strict), e.context().unwrap().policy);
+        assert_eq!(
+            "Configuration type mismatch for key 'str_int': expected u16, found String (rejected by strict coercion policy, source 'test')",
+            e.to_string()
+        );
+
+        let config = config.set_coercion_policy(CoercionPolicy::Default);
+        assert_eq!(8080, config.get::<u16>("str_int").unwrap());
+        assert!(config.get::<bool>("str_yes").unwrap());
+        assert!(config.get::<bool>("str_on").is_err());
+        assert!(config.get::<bool>("int").is_err());
+        assert!(config.get::<f64>("str_float").is_err());
+        assert!(config.get::<u16>("str_exp").is_err());
+        assert_eq!(3, config.get::<u8>("float").unwrap());
+        assert_eq!("1", config.get::<String>("int").unwrap());
+        assert!(config.get::<Ipv4Addr>("int").is_err());
+
+        let config = config.set_coercion_policy(CoercionPolicy::Lenient);
+        assert!(config.get::<bool>("str_on").unwrap());
+        assert!(config.get::<bool>("int").unwrap());
+        assert_eq!(1.5, config.get::<f64>("str_float").unwrap());
+        assert_eq!(1000, config.get::<u16>("str_exp").unwrap());
+        assert!(config.get::<Ipv4Addr>("int").is_err());
+        assert_eq!(1, config.get::<FromStrHolder<u8>>("int").unwrap().0);
+        match config
+            .get::<bool>("float")
+            .as_ref()
+            .map_err(ConfigError::kind)
+        {
+            Err(ConfigError::ConfigTypeMismatch(_, found, _)) => assert_eq!("Float", *found),
+            v => panic!("expected mismatch, got {:?}", v),
+        }
+    }
+
     #[test]
     fn from_value_for_float_types() {
         let mut context = TestContext::new();
@@ -1145,6 +1522,25 @@ mod test {
             parse_duration_from_str(&mut context.0.source.new_context(&mut context.1), "1x")
                 .is_err()
         );
+        // 溢出
+        for du in [
+            "18446744073709551616s",
+            "5124095576030432h",
+            "1000000000000000000000000000000000000000ns",
+        ] {
+            assert!(
+                parse_duration_from_str(&mut context.0.source.new_context(&mut context.1), du)
+                    .is_err()
+            );
+        }
+        assert_eq!(
+            parse_duration_from_str(
+                &mut context.0.source.new_context(&mut context.1),
+                "000000000000000000000000000000000000000001s"
+            )
+            .unwrap(),
+            Duration::new(1, 0)
+        );
     }
 
     #[test]
@@ -160,7 +160,7 @@ mod test {
     macro_rules! should_err {
         ($v:ident) => {
             assert_eq!(true, $v.is_err());
-            match $v.err().unwrap() {
+            match $v.err().unwrap().kind() {
                 ConfigError::RefValueRecursiveError => {}
                 e => {
                     println!("{:?}", e);